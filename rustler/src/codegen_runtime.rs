@@ -1,9 +1,16 @@
 //! Functions used by runtime generated code. Should not be used.
 
+use std::any::Any;
 use std::ffi::CString;
 use std::fmt;
+use std::panic;
+use std::ptr;
 
-use crate::{Encoder, Env, OwnedBinary, Term};
+use crate::resource::ResourceInitError;
+use crate::schedule::{SchedulerFlags, YieldState, Yielding};
+use crate::{
+    Decoder, Encoder, Env, Error, NifResult, OldPrivData, OwnedBinary, Resource, ResourceArc, Term,
+};
 
 // Names used by the `rustler::init!` macro or other generated code.
 pub use crate::wrapper::exception::raise_exception;
@@ -94,18 +101,364 @@ impl fmt::Debug for NifReturned {
     }
 }
 
+/// Return code of the `load` callback if the user's `load` or `upgrade` function failed.
+pub const LOAD_FAILED: c_int = 1;
+/// Return code of the `load` callback if the user's `load` or `upgrade` function panicked.
+pub const LOAD_PANICKED: c_int = 2;
+/// Return code of the `load` callback if a resource type could not be registered.
+pub const LOAD_RESOURCE_FAILED: c_int = 3;
+/// Return code of the `load` callback if the load info could not be decoded.
+pub const LOAD_BAD_LOAD_INFO: c_int = 4;
+
+/// A `load` function, with the load info decoding already applied.
+pub type LoadCallback = for<'a> fn(Env<'a>, Term<'a>) -> Result<Option<PrivData>, LoadError>;
+
+/// An `upgrade` function, with the load info decoding already applied. The second argument is
+/// the private data of the old library.
+pub type UpgradeCallback =
+    for<'a> fn(Env<'a>, OldPrivData<'a>, Term<'a>) -> Result<Option<PrivData>, LoadError>;
+
+/// Handles the `load` callback of a NIF library.
+///
+/// A panic in the user's callback or in a resource registration makes loading fail instead of
+/// unwinding into the VM. Failures are reported through the VM's logger, and the returned code,
+/// which the VM includes in the error of `erlang:load_nif/2`, tells the kind of failure apart.
+///
 /// # Unsafe
 ///
 /// This takes arguments, including raw pointers, that must be correct.
 pub unsafe fn handle_nif_init_call(
-    function: Option<for<'a> fn(Env<'a>, Term<'a>) -> bool>,
+    function: Option<LoadCallback>,
+    resources: &[ResourceRegistration],
+    r_env: NIF_ENV,
+    priv_data: *mut *mut c_void,
+    load_info: NIF_TERM,
+) -> c_int {
+    handle_init(resources, r_env, priv_data, |env| {
+        let load_info = Term::new(env, load_info);
+        function.map_or(Ok(None), |inner| inner(env, load_info))
+    })
+}
+
+/// Handles the `upgrade` callback of a NIF library, like `handle_nif_init_call`.
+///
+/// # Unsafe
+///
+/// This takes arguments, including raw pointers, that must be correct.
+pub unsafe fn handle_nif_upgrade_call(
+    function: UpgradeCallback,
+    resources: &[ResourceRegistration],
     r_env: NIF_ENV,
+    priv_data: *mut *mut c_void,
+    old_priv_data: *mut *mut c_void,
     load_info: NIF_TERM,
 ) -> c_int {
+    // The old library, and with it its private data, is only unloaded after the upgrade.
+    let old_priv_data = OldPrivData::new((*old_priv_data as *const Instance).as_ref());
+
+    handle_init(resources, r_env, priv_data, |env| {
+        function(env, old_priv_data, Term::new(env, load_info))
+    })
+}
+
+unsafe fn handle_init<F>(
+    resources: &[ResourceRegistration],
+    r_env: NIF_ENV,
+    priv_data: *mut *mut c_void,
+    function: F,
+) -> c_int
+where
+    F: for<'a> FnOnce(Env<'a>) -> Result<Option<PrivData>, LoadError>,
+{
     let env = Env::new(&(), r_env);
-    let term = Term::new(env, load_info);
+    // Threads started from here on, including those started by `function`, belong to the new
+    // instance.
+    let (instance, previous) = crate::thread::enter_instance();
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        for register in resources {
+            register(env).map_err(|err| LoadError {
+                code: LOAD_RESOURCE_FAILED,
+                message: Some(err.to_string()),
+            })?;
+        }
+
+        function(env)
+    }));
+
+    match result {
+        Ok(Ok(data)) => {
+            *priv_data = Box::into_raw(Box::new(Instance::new(instance, data))) as *mut c_void;
+            0
+        }
+        Ok(Err(err)) => {
+            crate::thread::abort_instance(instance, previous);
+            if let Some(message) = err.message {
+                log_error(r_env, &message);
+            }
+            err.code
+        }
+        Err(err) => {
+            crate::thread::abort_instance(instance, previous);
+            log_error(
+                r_env,
+                &format!(
+                    "panic while loading the NIF library: {}",
+                    panic_message(&err)
+                ),
+            );
+            LOAD_PANICKED
+        }
+    }
+}
+
+/// Decode `load_info` and pass it to the user's `load` or `upgrade` function.
+pub fn call_load<'a, A, R>(
+    env: Env<'a>,
+    load_info: Term<'a>,
+    function: fn(Env<'a>, A) -> R,
+) -> Result<Option<PrivData>, LoadError>
+where
+    A: Decoder<'a>,
+    R: LoadResult,
+{
+    let load_info = decode_load_info(env, load_info)?;
+    function(env, load_info).into_priv_data(env)
+}
+
+/// Decode `load_info` and pass it to the user's `upgrade` function, together with the private
+/// data of the old library.
+pub fn call_upgrade<'a, A, R>(
+    env: Env<'a>,
+    old_priv_data: OldPrivData<'a>,
+    load_info: Term<'a>,
+    function: fn(Env<'a>, OldPrivData<'a>, A) -> R,
+) -> Result<Option<PrivData>, LoadError>
+where
+    A: Decoder<'a>,
+    R: LoadResult,
+{
+    let load_info = decode_load_info(env, load_info)?;
+    function(env, old_priv_data, load_info).into_priv_data(env)
+}
+
+fn decode_load_info<'a, A: Decoder<'a>>(env: Env<'a>, load_info: Term<'a>) -> Result<A, LoadError> {
+    load_info.decode().map_err(|err| LoadError {
+        code: LOAD_BAD_LOAD_INFO,
+        message: Some(format!(
+            "could not decode the load info {:?}: {}",
+            load_info,
+            describe_error(env, err)
+        )),
+    })
+}
+
+/// # Unsafe
+///
+/// `r_env` and `priv_data` must be the arguments passed to the `unload` callback.
+pub unsafe fn handle_nif_unload_call(
+    function: Option<for<'a> fn(Env<'a>)>,
+    r_env: NIF_ENV,
+    priv_data: *mut c_void,
+) {
+    let env = Env::new(&(), r_env);
+    if let Some(function) = function {
+        if let Err(err) = panic::catch_unwind(|| function(env)) {
+            log_error(
+                r_env,
+                &format!(
+                    "panic while unloading the NIF library: {}",
+                    panic_message(&err)
+                ),
+            );
+        }
+    }
+
+    let instance = priv_data as *mut Instance;
+    if !instance.is_null() {
+        crate::thread::join_instance_threads((*instance).id);
+        drop(Box::from_raw(instance));
+    }
+}
+
+/// Report a failure while loading the library, like the failures of `rustler::init!`.
+pub fn report_load_error(env: Env, message: &str) {
+    log_error(env.as_c_arg(), message);
+}
+
+/// Report `message` as an error through the VM's logger.
+///
+/// The event is sent to the registered `logger` process as `{log, error, Format, Args, Meta}`,
+/// the same way the emulator reports its own errors, so it ends up wherever the application's
+/// logger handlers write to. `env` is the environment of the calling NIF or callback. Without an
+/// environment, or if no logger is running, the message is written to stderr instead.
+pub(crate) fn log_error(env: NIF_ENV, message: &str) {
+    if env.is_null() || !unsafe { send_to_logger(env, message) } {
+        eprintln!("rustler: {}", message);
+    }
+}
+
+// Unit tests run outside of the VM, where the `enif_*` functions cannot be linked.
+#[cfg(test)]
+unsafe fn send_to_logger(_caller_env: NIF_ENV, _message: &str) -> bool {
+    false
+}
+
+#[cfg(not(test))]
+unsafe fn send_to_logger(caller_env: NIF_ENV, message: &str) -> bool {
+    use crate::types::atom;
+
+    let caller = Env::new(&(), caller_env);
+    let logger = match caller.whereis_pid(atom::logger()) {
+        Some(logger) => logger,
+        None => return false,
+    };
+
+    let msg_env = rustler_sys::enif_alloc_env();
+    let env = Env::new(&(), msg_env);
+    let event = (
+        atom::log(),
+        atom::error(),
+        "rustler: ~ts",
+        vec![message],
+        Term::map_new(env),
+    )
+        .encode(env);
+    let res = rustler_sys::enif_send(caller_env, logger.as_c_arg(), msg_env, event.as_c_arg());
+    rustler_sys::enif_free_env(msg_env);
+
+    res != 0
+}
+
+/// Extract the message of a panic payload, as far as it is a string.
+pub(crate) fn panic_message(err: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = err.downcast_ref::<&'static str>() {
+        message
+    } else if let Some(message) = err.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+/// Private data of a NIF library, see `Env::priv_data`.
+pub type PrivData = Box<dyn Any + Send + Sync>;
+
+/// Its address tells apart the copies of the library code the VM has loaded. Reloading a module
+/// without changing its NIF library loads the same copy again.
+static LIBRARY: u8 = 0;
+
+/// What the VM keeps as the private data of a loaded instance of the library.
+///
+/// During an upgrade, the instance of the old library may come from another build of the code, so
+/// only the leading `repr(C)` fields can be read from it until `library` tells otherwise. Their
+/// layout must not change.
+#[repr(C)]
+pub(crate) struct Instance {
+    /// The copy of the library code that loaded the instance, see `LIBRARY`.
+    library: *const u8,
+    /// The value in `data`, or null.
+    data_ptr: *const c_void,
+    /// Tells apart the instances loaded from the same copy of the library code, which share the
+    /// threads started with `crate::thread`.
+    id: usize,
+    data: Option<PrivData>,
+}
+
+impl Instance {
+    fn new(id: usize, data: Option<PrivData>) -> Self {
+        Instance {
+            library: &LIBRARY,
+            data_ptr: data.as_ref().map_or(ptr::null(), |data| {
+                &**data as *const (dyn Any + Send + Sync) as *const c_void
+            }),
+            id,
+            data,
+        }
+    }
+
+    /// Whether the instance was loaded from the same copy of the library code as the caller.
+    pub(crate) fn is_current_library(&self) -> bool {
+        ptr::eq(self.library, &LIBRARY)
+    }
+
+    /// The private data of the instance, which must be of the current library.
+    pub(crate) fn data(&self) -> Option<&PrivData> {
+        debug_assert!(self.is_current_library());
+        self.data.as_ref()
+    }
+
+    pub(crate) fn data_ptr(&self) -> *const c_void {
+        self.data_ptr
+    }
+}
+
+/// Describes why loading the library failed.
+pub struct LoadError {
+    code: c_int,
+    message: Option<String>,
+}
+
+/// Return types of the `load` and `upgrade` callbacks of `rustler::init!`.
+pub trait LoadResult {
+    fn into_priv_data(self, env: Env) -> Result<Option<PrivData>, LoadError>;
+}
+
+impl LoadResult for bool {
+    fn into_priv_data(self, _env: Env) -> Result<Option<PrivData>, LoadError> {
+        if self {
+            Ok(None)
+        } else {
+            Err(LoadError {
+                code: LOAD_FAILED,
+                message: None,
+            })
+        }
+    }
+}
+
+impl<T> LoadResult for Option<T>
+where
+    T: Any + Send + Sync,
+{
+    fn into_priv_data(self, env: Env) -> Result<Option<PrivData>, LoadError> {
+        match self {
+            Some(data) => Ok(Some(Box::new(data))),
+            None => false.into_priv_data(env),
+        }
+    }
+}
+
+impl<T> LoadResult for Result<T, Error>
+where
+    T: Any + Send + Sync,
+{
+    fn into_priv_data(self, env: Env) -> Result<Option<PrivData>, LoadError> {
+        match self {
+            Ok(data) => Ok(Some(Box::new(data))),
+            Err(err) => Err(LoadError {
+                code: LOAD_FAILED,
+                message: Some(format!("load failed: {}", describe_error(env, err))),
+            }),
+        }
+    }
+}
 
-    function.map_or(0, |inner| i32::from(!inner(env, term)))
+/// Format an error including the terms it carries, which `Debug` for `Error` leaves out.
+fn describe_error(env: Env, err: Error) -> String {
+    match err {
+        Error::RaiseAtom(atom) => format!("error({})", atom),
+        Error::RaiseTerm(term) => format!("error({:?})", term.encode(env)),
+        Error::Term(term) => format!("{{error, {:?}}}", term.encode(env)),
+        err => format!("{:?}", err),
+    }
+}
+
+/// Registration of a single resource type listed in the `resources` option of `rustler::init!`.
+pub type ResourceRegistration = for<'a> fn(Env<'a>) -> Result<(), ResourceInitError>;
+
+pub fn register_resource<T: Resource>(env: Env) -> Result<(), ResourceInitError> {
+    env.register::<T>()
 }
 
 pub fn handle_nif_result<T>(
@@ -131,3 +484,139 @@ where
         }
     }
 }
+
+/// Take the state of a yielding NIF, which is passed as an extra argument after the first call.
+pub fn take_yield_state<S: Send + 'static>(args: &[Term], arity: usize) -> NifResult<Option<S>> {
+    match args.get(arity) {
+        None => Ok(None),
+        Some(term) => {
+            let state: ResourceArc<YieldState> = term.decode()?;
+            state.take().map(Some).ok_or(Error::BadArg)
+        }
+    }
+}
+
+/// Handle the result of a yielding NIF, rescheduling `fun` with the state unless it is `Done`.
+///
+/// `flags` is the scheduler the NIF is declared for, which `Yield` falls back to if the current
+/// scheduler cannot be determined.
+pub fn handle_yielding_result<'a, S, T>(
+    result: std::thread::Result<Result<Yielding<S, T>, crate::error::Error>>,
+    env: Env<'a>,
+    args: &[Term<'a>],
+    arity: usize,
+    fun_name: &str,
+    flags: SchedulerFlags,
+    fun: unsafe extern "C" fn(NIF_ENV, c_int, *const NIF_TERM) -> NIF_TERM,
+) -> NifReturned
+where
+    S: Send + 'static,
+    T: NifReturnable,
+{
+    let (flags, state) = match result {
+        Ok(Ok(Yielding::Yield(state))) => {
+            (crate::schedule::current_scheduler().unwrap_or(flags), state)
+        }
+        Ok(Ok(Yielding::Reschedule(flags, state))) => (flags, state),
+        Ok(Ok(Yielding::Done(value))) => return handle_nif_result(Ok(Ok(value)), env),
+        Ok(Err(err)) => return handle_nif_result::<T>(Ok(Err(err)), env),
+        Err(err) => return handle_nif_result::<T>(Err(err), env),
+    };
+
+    // Reuse the resource of the previous step, if any.
+    let state = match args
+        .get(arity)
+        .map(|term| term.decode::<ResourceArc<YieldState>>())
+    {
+        Some(Ok(resource)) => {
+            resource.put(state);
+            resource
+        }
+        _ => ResourceArc::new(YieldState::new(state)),
+    };
+
+    let mut args: Vec<NIF_TERM> = args[..arity].iter().map(|term| term.as_c_arg()).collect();
+    args.push(state.encode(env).as_c_arg());
+
+    NifReturned::Reschedule {
+        fun_name: CString::new(fun_name).unwrap(),
+        flags,
+        fun,
+        args,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        c_void, handle_nif_init_call, Instance, LoadError, PrivData, LOAD_FAILED, LOAD_PANICKED,
+    };
+    use crate::{Env, Term};
+    use std::ptr;
+
+    fn panicking_load(_env: Env, _load_info: Term) -> Result<Option<PrivData>, LoadError> {
+        panic!("bad load_info")
+    }
+
+    fn failing_load(_env: Env, _load_info: Term) -> Result<Option<PrivData>, LoadError> {
+        Err(LoadError {
+            code: LOAD_FAILED,
+            message: None,
+        })
+    }
+
+    fn priv_data_load(_env: Env, _load_info: Term) -> Result<Option<PrivData>, LoadError> {
+        Ok(Some(Box::new("data".to_string())))
+    }
+
+    #[test]
+    fn test_load_panic_is_caught() {
+        let mut priv_data = ptr::null_mut();
+        let res = unsafe {
+            handle_nif_init_call(
+                Some(panicking_load),
+                &[],
+                ptr::null_mut(),
+                &mut priv_data,
+                0,
+            )
+        };
+
+        assert_eq!(res, LOAD_PANICKED);
+        assert!(priv_data.is_null());
+    }
+
+    #[test]
+    fn test_load_failure() {
+        let mut priv_data = ptr::null_mut();
+        let res = unsafe {
+            handle_nif_init_call(Some(failing_load), &[], ptr::null_mut(), &mut priv_data, 0)
+        };
+
+        assert_eq!(res, LOAD_FAILED);
+    }
+
+    #[test]
+    fn test_load_priv_data() {
+        let mut priv_data = ptr::null_mut();
+        let res = unsafe {
+            handle_nif_init_call(
+                Some(priv_data_load),
+                &[],
+                ptr::null_mut(),
+                &mut priv_data,
+                0,
+            )
+        };
+
+        assert_eq!(res, 0);
+        let instance = unsafe { Box::from_raw(priv_data as *mut Instance) };
+        assert!(instance.is_current_library());
+        let data = instance.data().unwrap();
+        assert_eq!(data.downcast_ref::<String>().unwrap(), "data");
+        assert_eq!(
+            instance.data_ptr(),
+            data.downcast_ref::<String>().unwrap() as *const String as *const c_void
+        );
+    }
+}
@@ -1,7 +1,10 @@
+use crate::codegen_runtime::{c_void, Instance};
 use crate::types::LocalPid;
 use crate::wrapper::{NIF_ENV, NIF_TERM};
 use crate::{Encoder, Term};
+use std::any::Any;
 use std::marker::PhantomData;
+use std::mem::MaybeUninit;
 use std::ptr;
 use std::sync::{Arc, Weak};
 
@@ -146,6 +149,26 @@ impl<'a> Env<'a> {
         }
     }
 
+    /// Get the private data of the NIF library.
+    ///
+    /// The private data is the value returned by the `load` or `upgrade` callback given to
+    /// `rustler::init!`, see
+    /// [enif\_priv\_data](https://www.erlang.org/doc/man/erl_nif.html#enif_priv_data). It lives
+    /// until the library is unloaded.
+    ///
+    /// Returns `None` if the library has no private data, if it is not of type `T`, or if `self`
+    /// is not the environment of a process, like the environment of an `OwnedEnv`. Such an
+    /// environment belongs to no library, so it has no private data.
+    pub fn priv_data<T: Any + Send + Sync>(self) -> Option<&'a T> {
+        let mut pid = MaybeUninit::uninit();
+        if unsafe { rustler_sys::enif_self(self.as_c_arg(), pid.as_mut_ptr()) }.is_null() {
+            return None;
+        }
+
+        let instance = unsafe { rustler_sys::enif_priv_data(self.as_c_arg()) } as *const Instance;
+        unsafe { instance.as_ref() }?.data()?.downcast_ref()
+    }
+
     /// Decodes binary data to a term.
     ///
     /// Follows the erlang
@@ -165,6 +188,49 @@ impl<'a> Env<'a> {
     }
 }
 
+/// The private data of the old library, passed to the `upgrade` callback given to `rustler::init!`.
+///
+/// The old library may have been built from different code, where a type of the same name is not
+/// necessarily the same type. The private data is therefore only accessible as a Rust type if the
+/// old library was loaded from the same copy of the library code, which is the case when a module
+/// is reloaded without changing its NIF library. Private data that has to be carried over to a
+/// new build is read through `as_ptr()`, in a representation both versions agree on.
+#[derive(Clone, Copy)]
+pub struct OldPrivData<'a> {
+    instance: Option<&'a Instance>,
+}
+
+impl<'a> OldPrivData<'a> {
+    pub(crate) fn new(instance: Option<&'a Instance>) -> Self {
+        OldPrivData { instance }
+    }
+
+    /// Whether the old library was loaded from the same copy of the library code as the new one.
+    pub fn is_same_library(&self) -> bool {
+        self.instance
+            .map_or(false, |instance| instance.is_current_library())
+    }
+
+    /// The private data of the old library, if it is of type `T` and `is_same_library()` holds.
+    pub fn downcast_ref<T: Any + Send + Sync>(&self) -> Option<&'a T> {
+        let instance = self
+            .instance
+            .filter(|instance| instance.is_current_library())?;
+        instance.data()?.downcast_ref()
+    }
+
+    /// A pointer to the private data of the old library, or null if it has none.
+    ///
+    /// The pointer stays valid during the upgrade. It points to the value the old library
+    /// returned from its `load` or `upgrade` callback, whose type is only known to the old
+    /// library: reading it is sound if both versions agree on its layout, e.g. a `#[repr(C)]`
+    /// type that is kept unchanged between versions.
+    pub fn as_ptr(&self) -> *const c_void {
+        self.instance
+            .map_or(ptr::null(), |instance| instance.data_ptr())
+    }
+}
+
 /// A process-independent environment, a place where Erlang terms can be created outside of a NIF
 /// call.
 ///
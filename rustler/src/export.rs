@@ -14,6 +14,11 @@
 ///
 /// The third argument is an `Option<fn(env: &Env, load_info: Term) -> bool>`. If this is
 /// `Some`, the function will execute when the NIF is first loaded by the BEAM.
+///
+/// Folds from `rustler::schedule` can be returned from the exported functions, and threads started
+/// with `rustler::thread` are joined when the library is unloaded. Hot code upgrades, resource
+/// registration through the `resources` option and yielding NIFs are only supported by
+/// `rustler::init!`.
 #[macro_export]
 #[deprecated(since = "0.22.0", note = "Please use `rustler::init!` instead.")]
 macro_rules! rustler_export_nifs {
@@ -25,18 +30,43 @@ macro_rules! rustler_export_nifs {
         static mut NIF_ENTRY: Option<$crate::codegen_runtime::DEF_NIF_ENTRY> = None;
 
         $crate::rustler_export_nifs!(internal_platform_init, ({
-            // TODO: If an unwrap ever happens, we will unwind right into C! Fix this!
-
             extern "C" fn nif_load(
                 env: $crate::codegen_runtime::NIF_ENV,
-                _priv_data: *mut *mut $crate::codegen_runtime::c_void,
+                priv_data: *mut *mut $crate::codegen_runtime::c_void,
                 load_info: $crate::codegen_runtime::NIF_TERM)
                 -> $crate::codegen_runtime::c_int {
+                fn on_load<'a>(
+                    env: $crate::Env<'a>,
+                    load_info: $crate::Term<'a>,
+                ) -> Result<
+                    Option<$crate::codegen_runtime::PrivData>,
+                    $crate::codegen_runtime::LoadError,
+                > {
+                    const ON_LOAD: Option<for<'a> fn($crate::Env<'a>, $crate::Term<'a>) -> bool> =
+                        $on_load;
+                    match ON_LOAD {
+                        Some(function) => $crate::codegen_runtime::call_load(env, load_info, function),
+                        None => Ok(None),
+                    }
+                }
+
+                // The exported functions are plain functions, which may return a fold, so the
+                // state of a fold is always registered.
+                const RESOURCES: &[$crate::codegen_runtime::ResourceRegistration] = &[
+                    $crate::codegen_runtime::register_resource::<$crate::schedule::YieldState>,
+                ];
+
                 unsafe {
-                    $crate::codegen_runtime::handle_nif_init_call($on_load, env, load_info)
+                    $crate::codegen_runtime::handle_nif_init_call(Some(on_load), RESOURCES, env, priv_data, load_info)
                 }
             }
 
+            extern "C" fn nif_unload(
+                env: $crate::codegen_runtime::NIF_ENV,
+                priv_data: *mut $crate::codegen_runtime::c_void) {
+                unsafe { $crate::codegen_runtime::handle_nif_unload_call(None, env, priv_data) }
+            }
+
             const FUN_ENTRIES: &'static [$crate::codegen_runtime::DEF_NIF_FUNC] = &[
                 $($crate::rustler_export_nifs!(internal_item_init, $exported_nif)),*
             ];
@@ -50,7 +80,7 @@ macro_rules! rustler_export_nifs {
                 load: Some(nif_load),
                 reload: None,
                 upgrade: None,
-                unload: None,
+                unload: Some(nif_unload),
                 vm_variant: b"beam.vanilla\x00".as_ptr() as *const $crate::codegen_runtime::c_char,
                 options: 0,
                 sizeof_ErlNifResourceTypeInit: $crate::codegen_runtime::get_nif_resource_type_init_size(),
@@ -0,0 +1,216 @@
+//! A queue of binaries for buffering incoming data without copying it.
+
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::mem::MaybeUninit;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use rustler_sys::{c_int, ErlNifIOQueue, ErlNifIOQueueOpts, SysIOVec};
+
+use crate::{Binary, Env, IoVec, NifResult, OwnedBinary, Term};
+
+/// A queue of binaries, see
+/// [ErlNifIOQueue](https://www.erlang.org/doc/man/erl_nif.html#ErlNifIOQueue) in the Erlang docs.
+///
+/// The queue keeps references to the binaries pushed into it instead of copying their data, and
+/// hands the data out as a sequence of slices. It implements `Read` and `BufRead`, so a parser
+/// can consume incoming data directly from the queue.
+///
+/// An `IoQueue` can be sent to other threads but not shared between them. To keep one in a
+/// resource, wrap it in a `Mutex`:
+///
+/// ```ignore
+/// struct Connection {
+///     buffer: Mutex<IoQueue>,
+/// }
+///
+/// impl Resource for Connection {}
+///
+/// #[rustler::nif]
+/// fn feed(connection: ResourceArc<Connection>, data: Term) -> NifResult<usize> {
+///     let mut buffer = connection.buffer.lock().unwrap();
+///     buffer.push_term(data)?;
+///     Ok(buffer.len())
+/// }
+/// ```
+pub struct IoQueue {
+    inner: NonNull<ErlNifIOQueue>,
+}
+
+unsafe impl Send for IoQueue {}
+
+impl IoQueue {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        let inner = unsafe { rustler_sys::enif_ioq_create(ErlNifIOQueueOpts::ERL_NIF_IOQ_NORMAL) };
+        IoQueue {
+            inner: NonNull::new(inner).expect("enif_ioq_create: allocation failed"),
+        }
+    }
+
+    fn as_c_arg(&self) -> *mut ErlNifIOQueue {
+        self.inner.as_ptr()
+    }
+
+    /// The number of bytes in the queue.
+    pub fn len(&self) -> usize {
+        unsafe { rustler_sys::enif_ioq_size(self.as_c_arg()) }
+    }
+
+    /// Whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Push the data of `binary` to the back of the queue.
+    pub fn push(&mut self, binary: OwnedBinary) {
+        let mut binary = binary.into_raw();
+        // Ownership of the binary is transferred to the queue. This only fails if the number of
+        // bytes to skip is larger than the binary.
+        unsafe { rustler_sys::enif_ioq_enq_binary(self.as_c_arg(), &mut binary, 0) };
+    }
+
+    /// Push the binaries of `iovec` to the back of the queue.
+    ///
+    /// The queue references the binaries of `iovec` instead of copying them.
+    pub fn push_iovec(&mut self, iovec: &IoVec) {
+        // This only fails if the number of bytes to skip is larger than the iovec.
+        unsafe { rustler_sys::enif_ioq_enqv(self.as_c_arg(), iovec.as_c_arg(), 0) };
+    }
+
+    /// Push a binary term, or a list of binaries like the ones returned by
+    /// `erlang:iolist_to_iovec/1`, to the back of the queue.
+    ///
+    /// Large binaries are referenced by the queue instead of being copied. Returns
+    /// `Err(Error::BadArg)` if `term` is neither a binary nor a list of binaries.
+    pub fn push_term(&mut self, term: Term) -> NifResult<()> {
+        self.push_iovec(&IoVec::from_term(term)?);
+        Ok(())
+    }
+
+    /// The slices of data in the queue, from front to back.
+    pub fn chunks(&self) -> Chunks<'_> {
+        let mut count: c_int = 0;
+        unsafe {
+            let iov = rustler_sys::enif_ioq_peek(self.as_c_arg(), &mut count);
+            Chunks::from_raw(iov, count)
+        }
+    }
+
+    /// The first slice of data in the queue, or an empty slice if the queue is empty.
+    pub fn peek(&self) -> &[u8] {
+        self.chunks().next().unwrap_or(&[])
+    }
+
+    /// The first slice of data in the queue as a binary term, without copying it.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub fn peek_head<'a>(&self, env: Env<'a>) -> Option<Binary<'a>> {
+        let mut head = MaybeUninit::uninit();
+        let success = unsafe {
+            rustler_sys::enif_ioq_peek_head(
+                env.as_c_arg(),
+                self.as_c_arg(),
+                ptr::null_mut(),
+                head.as_mut_ptr(),
+            )
+        };
+        if success == 0 {
+            return None;
+        }
+
+        let head = unsafe { Term::new(env, head.assume_init()) };
+        Binary::from_term(head).ok()
+    }
+
+    /// Remove `count` bytes from the front of the queue.
+    ///
+    /// Returns `false`, and leaves the queue untouched, if the queue holds less than `count`
+    /// bytes.
+    #[must_use]
+    pub fn dequeue(&mut self, count: usize) -> bool {
+        let success = unsafe { rustler_sys::enif_ioq_deq(self.as_c_arg(), count, ptr::null_mut()) };
+        success != 0
+    }
+}
+
+impl Default for IoQueue {
+    fn default() -> Self {
+        IoQueue::new()
+    }
+}
+
+impl fmt::Debug for IoQueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IoQueue").field("len", &self.len()).finish()
+    }
+}
+
+impl Drop for IoQueue {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_ioq_destroy(self.as_c_arg()) };
+    }
+}
+
+impl Read for IoQueue {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        for chunk in self.chunks() {
+            let count = chunk.len().min(buf.len() - read);
+            buf[read..read + count].copy_from_slice(&chunk[..count]);
+            read += count;
+            if read == buf.len() {
+                break;
+            }
+        }
+
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl BufRead for IoQueue {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.peek())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        assert!(self.dequeue(amt), "cannot consume more bytes than queued");
+    }
+}
+
+/// An iterator over the slices of data in an `IoQueue` or an `IoVec`.
+pub struct Chunks<'a> {
+    iov: slice::Iter<'a, SysIOVec>,
+}
+
+impl<'a> Chunks<'a> {
+    /// Iterate over the `count` vectors at `iov`, which must stay valid for `'a`.
+    pub(crate) unsafe fn from_raw(iov: *const SysIOVec, count: c_int) -> Self {
+        let iov = if iov.is_null() || count <= 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(iov, count as usize)
+        };
+        Chunks { iov: iov.iter() }
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a [u8];
+
+    // `iov_len` is an `unsigned long` on Windows.
+    #[allow(clippy::unnecessary_cast)]
+    fn next(&mut self) -> Option<&'a [u8]> {
+        self.iov.next().map(|iov| unsafe {
+            slice::from_raw_parts(iov.iov_base as *const u8, iov.iov_len as usize)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iov.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Chunks<'_> {}
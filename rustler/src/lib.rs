@@ -38,15 +38,16 @@ mod term;
 
 pub use crate::term::Term;
 pub use crate::types::{
-    Atom, Binary, Decoder, Encoder, ErlOption, ListIterator, LocalPid, MapIterator, NewBinary,
-    OwnedBinary,
+    Atom, Binary, BinaryReader, Bitstring, Decoder, Encoder, ErlOption, IoData, IoVec,
+    ListIterator, LocalPid, LocalPort, MapIterator, NewBinary, OwnedBinary, OwnedBinaryWriter,
+    OwnedReference, Reference,
 };
 
 #[cfg(feature = "big_integer")]
 pub use crate::types::BigInt;
 
 pub mod resource;
-pub use crate::resource::ResourceArc;
+pub use crate::resource::{Monitor, Resource, ResourceArc, SelectMode};
 
 #[doc(hidden)]
 pub mod dynamic;
@@ -55,13 +56,22 @@ pub use crate::dynamic::TermType;
 pub mod schedule;
 pub use crate::schedule::SchedulerFlags;
 pub mod env;
-pub use crate::env::{Env, OwnedEnv};
+pub use crate::env::{Env, OldPrivData, OwnedEnv};
+pub mod sync;
+pub mod system;
+pub use crate::system::{system_info, SystemInfo};
+pub mod task;
 pub mod thread;
-pub use crate::thread::{spawn, JobSpawner, ThreadSpawner};
+pub mod time;
+pub use crate::thread::{
+    spawn, ErlangThreadSpawner, JobSpawner, PoolSpawner, ThreadSpawner, TsdKey,
+};
 
 pub mod error;
 pub mod export;
+pub mod io_queue;
 pub use crate::error::Error;
+pub use crate::io_queue::IoQueue;
 
 pub mod r#return;
 pub use crate::r#return::Return;
@@ -4,6 +4,10 @@ pub trait Nif {
     const NAME: *const c_char;
     const ARITY: c_uint;
     const FLAGS: c_uint;
+    /// Whether the NIF keeps state between calls in a `YieldState` resource, which then has to
+    /// be registered when the library is loaded.
+    #[doc(hidden)]
+    const USES_YIELD_STATE: bool = false;
     const FUNC: DEF_NIF_FUNC;
     const RAW_FUNC: unsafe extern "C" fn(
         nif_env: NIF_ENV,
@@ -3,44 +3,180 @@
 //! A NIF resource allows you to safely store Rust structs in a term, and therefore keep it across
 //! NIF calls. The struct will be automatically dropped when the BEAM GC decides that there are no
 //! more references to the resource.
+//!
+//! Every type that is stored in a resource has to be registered while the NIF library is loaded,
+//! by implementing [`Resource`] and calling [`Env::register`]. The trait's optional callbacks map to
+//! the members of
+//! [ErlNifResourceTypeInit](https://www.erlang.org/doc/man/erl_nif.html#ErlNifResourceTypeInit).
 
-use std::marker::PhantomData;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt;
 use std::mem;
 use std::ops::Deref;
+use std::panic;
 use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
 
-use super::{Binary, Decoder, Encoder, Env, Error, NifResult, Term};
+#[cfg(feature = "nif_version_2_16")]
+use super::Atom;
+use super::{Binary, Decoder, Encoder, Env, Error, LocalPid, NifResult, Term};
+use crate::codegen_runtime::{log_error, panic_message};
 use crate::wrapper::{
-    c_void, resource, NifResourceFlags, MUTABLE_NIF_RESOURCE_HANDLE, NIF_ENV, NIF_RESOURCE_TYPE,
+    c_int, c_void, resource, ErlNifMonitor, ErlNifPid, NifResourceFlags, NifResourceTypeInit,
+    MUTABLE_NIF_RESOURCE_HANDLE, NIF_ENV, NIF_RESOURCE_TYPE,
 };
 
+mod monitor;
+mod select;
+pub use monitor::Monitor;
+pub use select::{Event, SelectError, SelectMode, SelectStatus};
+
 /// Re-export a type used by the `resource!` macro.
 #[doc(hidden)]
 pub use crate::wrapper::NIF_RESOURCE_FLAGS;
 
-/// The ResourceType struct contains a  NIF_RESOURCE_TYPE and a phantom reference to the type it
-/// is for. It serves as a holder for the information needed to interact with the Erlang VM about
-/// the resource type.
+/// Trait that has to be implemented by every type that is registered with [`Env::register`].
 ///
-/// This is usually stored in an implementation of ResourceTypeProvider.
-#[doc(hidden)]
-pub struct ResourceType<T> {
-    pub res: NIF_RESOURCE_TYPE,
-    pub struct_type: PhantomData<T>,
+/// All callbacks are optional. Apart from `destructor`, which is always registered, a callback is
+/// only registered with the VM if the corresponding `IMPLEMENTS_*` constant is set to `true`, so it
+/// is not enough to only override the method.
+///
+/// ```ignore
+/// struct Connection {
+///     owner: Mutex<Option<Monitor>>,
+/// }
+///
+/// impl rustler::Resource for Connection {
+///     const IMPLEMENTS_DOWN: bool = true;
+///
+///     fn down<'a>(&'a self, _env: Env<'a>, _pid: LocalPid, _monitor: Monitor) {
+///         // The owning process died, release the connection.
+///     }
+/// }
+/// ```
+pub trait Resource: Sized + Send + Sync + 'static {
+    /// Whether the `down` callback should be registered for this type.
+    const IMPLEMENTS_DOWN: bool = false;
+
+    /// Whether the `stop` callback should be registered for this type.
+    const IMPLEMENTS_STOP: bool = false;
+
+    /// Whether the `dyncall` callback should be registered for this type.
+    #[cfg(feature = "nif_version_2_16")]
+    const IMPLEMENTS_DYNCALL: bool = false;
+
+    /// The name the resource type is registered under with the VM, see [`Env::register`].
+    ///
+    /// The default is `std::any::type_name`, which is not guaranteed to be stable across compiler
+    /// versions or when the type is moved to another module. Types whose resources have to be
+    /// taken over by an upgraded version of the library, or that are called through
+    /// [`Env::dynamic_resource_call`], should override it with a fixed name.
+    fn type_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Called when the last reference to the resource is gone, right before the value is freed.
+    ///
+    /// The default implementation simply drops `self`.
+    #[allow(unused)]
+    fn destructor(self, env: Env<'_>) {}
+
+    /// Called when a process that is monitored through [`ResourceArc::monitor`] exits.
+    #[allow(unused)]
+    fn down<'a>(&'a self, env: Env<'a>, pid: LocalPid, monitor: Monitor) {}
+
+    /// Called once it is safe to close an event that was deselected with `SelectMode::Stop`, see
+    /// [`ResourceArc::select`].
+    ///
+    /// `is_direct_call` is `true` if the callback is called directly from `select`, in which case
+    /// `env` is the environment of the caller of `select`.
+    #[allow(unused)]
+    fn stop<'a>(&'a self, env: Env<'a>, event: Event, is_direct_call: bool) {}
+
+    /// Called through [`Env::dynamic_resource_call`], possibly from another NIF library.
+    ///
+    /// `call_data` is passed through unchanged; caller and callee have to agree on what it points
+    /// to.
+    #[cfg(feature = "nif_version_2_16")]
+    #[allow(unused)]
+    fn dyncall<'a>(&'a self, env: Env<'a>, call_data: *mut c_void) {}
 }
 
-/// This trait gets implemented for the type we want to put into a resource when
-/// resource! is called on it. It provides the ResourceType.
+/// Error that is returned when a resource type could not be opened.
+#[derive(Clone, Copy, Debug)]
+pub struct ResourceInitError {
+    type_name: &'static str,
+}
+
+impl ResourceInitError {
+    /// The Rust type that could not be registered.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for ResourceInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to register resource type `{}`", self.type_name)
+    }
+}
+
+impl std::error::Error for ResourceInitError {}
+
+/// Error that is returned by [`Env::dynamic_resource_call`] if `resource` is not a resource of
+/// the given type or the type does not implement `dyncall`.
+#[cfg(feature = "nif_version_2_16")]
+#[derive(Clone, Copy, Debug)]
+pub struct DynamicResourceCallError;
+
+type ResourceTypes = HashMap<TypeId, usize>;
+
+/// Resource type handles by Rust type, filled in by `open_struct_resource_type`.
 ///
-/// In most cases the user should not have to worry about this.
-#[doc(hidden)]
-pub trait ResourceTypeProvider: Sized + Send + Sync + 'static {
-    fn get_type() -> &'static ResourceType<Self>;
+/// Types are only registered while the library is loaded or upgraded, but they are looked up
+/// whenever a resource is created or decoded, on any scheduler. A registration therefore publishes
+/// a new copy of the table, and lookups read the current copy without taking a lock.
+static RESOURCE_TYPES: AtomicPtr<ResourceTypes> = AtomicPtr::new(ptr::null_mut());
+
+/// All copies of the table published so far. Lookups may still read a replaced copy, so they are
+/// kept alive, boxed to keep their addresses.
+#[allow(clippy::vec_box)]
+type PublishedResourceTypes = Vec<Box<ResourceTypes>>;
+
+lazy_static::lazy_static! {
+    /// Serializes the registrations.
+    static ref RESOURCE_TYPE_TABLES: Mutex<PublishedResourceTypes> = Mutex::new(Vec::new());
+}
+
+fn get_resource_type<T: 'static>() -> NIF_RESOURCE_TYPE {
+    let types = unsafe { RESOURCE_TYPES.load(Ordering::Acquire).as_ref() };
+    let res = types
+        .and_then(|types| types.get(&TypeId::of::<T>()))
+        .expect(
+            "The resource type hasn't been initialized. Did you remember to register it in `load`?",
+        );
+    *res as NIF_RESOURCE_TYPE
+}
+
+fn set_resource_type<T: 'static>(res: NIF_RESOURCE_TYPE) {
+    let mut tables = RESOURCE_TYPE_TABLES
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let mut types = tables
+        .last()
+        .map_or_else(HashMap::new, |types| (**types).clone());
+    types.insert(TypeId::of::<T>(), res as usize);
+
+    let types = Box::new(types);
+    RESOURCE_TYPES.store(&*types as *const ResourceTypes as *mut _, Ordering::Release);
+    tables.push(types);
 }
 
 impl<T> Encoder for ResourceArc<T>
 where
-    T: ResourceTypeProvider,
+    T: Send + Sync + 'static,
 {
     fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
         self.as_term(env)
@@ -48,48 +184,242 @@ where
 }
 impl<'a, T> Decoder<'a> for ResourceArc<T>
 where
-    T: ResourceTypeProvider + 'a,
+    T: Send + Sync + 'static,
 {
     fn decode(term: Term<'a>) -> NifResult<Self> {
         ResourceArc::from_term(term)
     }
 }
 
-/// Drop a T that lives in an Erlang resource. (erlang_nif-sys requires us to declare this
-/// function safe, but it is of course thoroughly unsafe!)
-extern "C" fn resource_destructor<T>(_env: NIF_ENV, handle: MUTABLE_NIF_RESOURCE_HANDLE) {
-    unsafe {
-        let aligned = align_alloced_mem_for_struct::<T>(handle);
-        let res = aligned as *mut T;
-        ptr::read(res);
+/// Run the resource callback `name` of `T`, reporting a panic instead of unwinding into the VM.
+fn catch_callback_panic<T: 'static>(env: NIF_ENV, name: &str, callback: impl FnOnce()) {
+    if let Err(err) = panic::catch_unwind(panic::AssertUnwindSafe(callback)) {
+        log_error(
+            env,
+            &format!(
+                "panic in the `{}` callback of resource type `{}`: {}",
+                name,
+                std::any::type_name::<T>(),
+                panic_message(&err)
+            ),
+        );
     }
 }
 
-/// This is the function that gets called from resource! in on_load to create a new
-/// resource type.
+/// Drop a T that lives in an Erlang resource, passing it to `Resource::destructor` first.
+/// (erlang_nif-sys requires us to declare this function safe, but it is of course thoroughly
+/// unsafe!)
+extern "C" fn resource_destructor<T: Resource>(env: NIF_ENV, handle: MUTABLE_NIF_RESOURCE_HANDLE) {
+    catch_callback_panic::<T>(env, "destructor", || unsafe {
+        let env = Env::new(&env, env);
+        let aligned = align_alloced_mem_for_struct::<T>(handle);
+        let res = ptr::read(aligned as *mut T);
+        res.destructor(env);
+    });
+}
+
+/// Drop a T that lives in an Erlang resource registered through the deprecated `resource!`
+/// macro, which does not implement `Resource`.
+extern "C" fn resource_drop<T: 'static>(env: NIF_ENV, handle: MUTABLE_NIF_RESOURCE_HANDLE) {
+    catch_callback_panic::<T>(env, "destructor", || unsafe {
+        let aligned = align_alloced_mem_for_struct::<T>(handle);
+        ptr::drop_in_place(aligned as *mut T);
+    });
+}
+
+/// Forward a process exit to `Resource::down` of the monitoring resource.
+extern "C" fn resource_down<T: Resource>(
+    env: NIF_ENV,
+    handle: MUTABLE_NIF_RESOURCE_HANDLE,
+    pid: *const ErlNifPid,
+    mon: *const ErlNifMonitor,
+) {
+    catch_callback_panic::<T>(env, "down", || unsafe {
+        let env = Env::new(&env, env);
+        let aligned = align_alloced_mem_for_struct::<T>(handle);
+        let res = &*(aligned as *const T);
+        let pid = LocalPid::from_c_arg(*pid);
+        let mon = Monitor::from_c_arg(*mon);
+
+        res.down(env, pid, mon);
+    });
+}
+
+/// Forward a finished `SelectMode::Stop` to `Resource::stop` of the selecting resource.
+extern "C" fn resource_stop<T: Resource>(
+    env: NIF_ENV,
+    handle: MUTABLE_NIF_RESOURCE_HANDLE,
+    event: Event,
+    is_direct_call: c_int,
+) {
+    catch_callback_panic::<T>(env, "stop", || unsafe {
+        let env = Env::new(&env, env);
+        let aligned = align_alloced_mem_for_struct::<T>(handle);
+        let res = &*(aligned as *const T);
+
+        res.stop(env, event, is_direct_call != 0);
+    });
+}
+
+/// Forward an `enif_dynamic_resource_call` to `Resource::dyncall`.
+#[cfg(feature = "nif_version_2_16")]
+extern "C" fn resource_dyncall<T: Resource>(
+    env: NIF_ENV,
+    handle: MUTABLE_NIF_RESOURCE_HANDLE,
+    call_data: *const c_void,
+) {
+    catch_callback_panic::<T>(env, "dyncall", || unsafe {
+        let env = Env::new(&env, env);
+        let aligned = align_alloced_mem_for_struct::<T>(handle);
+        let res = &*(aligned as *const T);
+
+        res.dyncall(env, call_data as *mut c_void);
+    });
+}
+
+/// This is the function that gets called from `Env::register` and `resource!` in on_load to
+/// create a new resource type.
 ///
 /// # Panics
 ///
 /// Panics if `name` isn't null-terminated.
 #[doc(hidden)]
-pub fn open_struct_resource_type<T: ResourceTypeProvider>(
+pub fn open_struct_resource_type<T: Resource>(
     env: Env,
     name: &str,
     flags: NifResourceFlags,
-) -> Option<ResourceType<T>> {
-    let res: Option<NIF_RESOURCE_TYPE> = unsafe {
-        resource::open_resource_type(
-            env.as_c_arg(),
-            name.as_bytes(),
-            Some(resource_destructor::<T>),
-            flags,
-        )
+) -> Result<(), ResourceInitError> {
+    let init = NifResourceTypeInit {
+        dtor: Some(resource_destructor::<T>),
+        stop: if T::IMPLEMENTS_STOP {
+            Some(resource_stop::<T>)
+        } else {
+            None
+        },
+        down: if T::IMPLEMENTS_DOWN {
+            Some(resource_down::<T>)
+        } else {
+            None
+        },
+        #[cfg(not(feature = "nif_version_2_16"))]
+        members: 3,
+        #[cfg(not(feature = "nif_version_2_16"))]
+        dyncall: None,
+        #[cfg(feature = "nif_version_2_16")]
+        members: 4,
+        #[cfg(feature = "nif_version_2_16")]
+        dyncall: if T::IMPLEMENTS_DYNCALL {
+            Some(resource_dyncall::<T>)
+        } else {
+            None
+        },
     };
 
-    res.map(|r| ResourceType {
-        res: r,
-        struct_type: PhantomData,
-    })
+    open_resource_type::<T>(env, name, &init, flags)
+}
+
+/// This is the function that gets called from the deprecated `resource!` macro to create a new
+/// resource type for a type that does not implement `Resource`. Only a destructor that drops the
+/// value is registered.
+///
+/// # Panics
+///
+/// Panics if `name` isn't null-terminated.
+#[doc(hidden)]
+pub fn open_plain_resource_type<T: Send + Sync + 'static>(
+    env: Env,
+    name: &str,
+    flags: NifResourceFlags,
+) -> Result<(), ResourceInitError> {
+    let init = NifResourceTypeInit {
+        dtor: Some(resource_drop::<T>),
+        stop: None,
+        down: None,
+        #[cfg(not(feature = "nif_version_2_16"))]
+        members: 3,
+        #[cfg(feature = "nif_version_2_16")]
+        members: 4,
+        dyncall: None,
+    };
+
+    open_resource_type::<T>(env, name, &init, flags)
+}
+
+fn open_resource_type<T: 'static>(
+    env: Env,
+    name: &str,
+    init: &NifResourceTypeInit,
+    flags: NifResourceFlags,
+) -> Result<(), ResourceInitError> {
+    #[cfg(feature = "nif_version_2_16")]
+    let res: Option<NIF_RESOURCE_TYPE> =
+        unsafe { resource::init_resource_type(env.as_c_arg(), name.as_bytes(), init, flags) };
+    #[cfg(not(feature = "nif_version_2_16"))]
+    let res: Option<NIF_RESOURCE_TYPE> =
+        unsafe { resource::open_resource_type_x(env.as_c_arg(), name.as_bytes(), init, flags) };
+
+    let res = res.ok_or(ResourceInitError {
+        type_name: std::any::type_name::<T>(),
+    })?;
+    set_resource_type::<T>(res);
+
+    Ok(())
+}
+
+impl<'a> Env<'a> {
+    /// Register the resource type `T` with the VM.
+    ///
+    /// This has to be called from the `load` or `upgrade` callback of the NIF library, once for
+    /// every type that is going to be stored in a `ResourceArc`. The type is registered under
+    /// `Resource::type_name`.
+    ///
+    /// During an upgrade, a type of the same name that was registered by the old library is taken
+    /// over, together with all of its existing resources, which from then on are handled by the
+    /// callbacks of the new library. This is only sound if both versions use the same name for
+    /// the same data, with an unchanged layout. Give a type a new name when its layout changes,
+    /// the resources of the old type are then kept by the old library until they are freed.
+    pub fn register<T: Resource>(self) -> Result<(), ResourceInitError> {
+        let name = format!("{}\x00", T::type_name());
+        open_struct_resource_type::<T>(
+            self,
+            &name,
+            NIF_RESOURCE_FLAGS::ERL_NIF_RT_CREATE_OR_TAKEOVER,
+        )
+    }
+
+    /// Call the `dyncall` callback of the resource `resource`, see
+    /// [enif\_dynamic\_resource\_call](https://www.erlang.org/doc/man/erl_nif.html#enif_dynamic_resource_call).
+    ///
+    /// The resource type is looked up by the module that registered it and its name, which is
+    /// `Resource::type_name` for resources registered with [`Env::register`]. This
+    /// makes it possible to call into resources of other NIF libraries.
+    ///
+    /// # Safety
+    ///
+    /// `call_data` is handed to `Resource::dyncall` as is, the callee must interpret it the way
+    /// the caller intended.
+    #[cfg(feature = "nif_version_2_16")]
+    pub unsafe fn dynamic_resource_call(
+        self,
+        module: Atom,
+        name: Atom,
+        resource: Term,
+        call_data: *mut c_void,
+    ) -> Result<(), DynamicResourceCallError> {
+        let res = rustler_sys::enif_dynamic_resource_call(
+            self.as_c_arg(),
+            module.as_c_arg(),
+            name.as_c_arg(),
+            resource.as_c_arg(),
+            call_data,
+        );
+
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(DynamicResourceCallError)
+        }
+    }
 }
 
 fn get_alloc_size_struct<T>() -> usize {
@@ -115,25 +445,25 @@ unsafe fn align_alloced_mem_for_struct<T>(ptr: *const c_void) -> *const c_void {
 /// convert back and forth between the two using `Encoder` and `Decoder`.
 pub struct ResourceArc<T>
 where
-    T: ResourceTypeProvider,
+    T: Send + Sync + 'static,
 {
     raw: *const c_void,
     inner: *mut T,
 }
 
 // Safe because T is `Sync` and `Send`.
-unsafe impl<T> Send for ResourceArc<T> where T: ResourceTypeProvider {}
-unsafe impl<T> Sync for ResourceArc<T> where T: ResourceTypeProvider {}
+unsafe impl<T> Send for ResourceArc<T> where T: Send + Sync + 'static {}
+unsafe impl<T> Sync for ResourceArc<T> where T: Send + Sync + 'static {}
 
 impl<T> ResourceArc<T>
 where
-    T: ResourceTypeProvider,
+    T: Send + Sync + 'static,
 {
-    /// Makes a new ResourceArc from the given type. Note that the type must have
-    /// ResourceTypeProvider implemented for it. See module documentation for info on this.
+    /// Makes a new ResourceArc from the given type. Note that the type has to be registered. See
+    /// module documentation for info on this.
     pub fn new(data: T) -> Self {
         let alloc_size = get_alloc_size_struct::<T>();
-        let mem_raw = unsafe { resource::alloc_resource(T::get_type().res, alloc_size) };
+        let mem_raw = unsafe { resource::alloc_resource(get_resource_type::<T>(), alloc_size) };
         let aligned_mem = unsafe { align_alloced_mem_for_struct::<T>(mem_raw) as *mut T };
 
         unsafe { ptr::write(aligned_mem, data) };
@@ -189,7 +519,7 @@ where
             resource::get_resource(
                 term.get_env().as_c_arg(),
                 term.as_c_arg(),
-                T::get_type().res,
+                get_resource_type::<T>(),
             )
         } {
             Some(res) => res,
@@ -205,6 +535,29 @@ where
         })
     }
 
+    /// Start monitoring the process `pid` from this resource.
+    ///
+    /// When the process exits, `Resource::down` is called on the resource. This requires
+    /// `T::IMPLEMENTS_DOWN` to be set.
+    ///
+    /// `caller_env` has to be the environment of the calling process when this is called from a
+    /// NIF, and `None` when called from a thread that is not managed by the Erlang VM.
+    ///
+    /// Returns `None` if the process is not alive anymore or if `T` does not implement `down`.
+    pub fn monitor(&self, caller_env: Option<Env>, pid: &LocalPid) -> Option<Monitor> {
+        let env = maybe_env(caller_env);
+        unsafe { resource::monitor_process(env, self.raw, pid.as_c_arg()) }.map(Monitor::from_c_arg)
+    }
+
+    /// Remove a monitor created by `monitor`.
+    ///
+    /// Returns `true` if the monitor was active, `false` if it was already removed or if the
+    /// monitored process has already exited.
+    pub fn demonitor(&self, caller_env: Option<Env>, mon: &Monitor) -> bool {
+        let env = maybe_env(caller_env);
+        unsafe { resource::demonitor_process(env, self.raw, mon.as_c_arg()) }
+    }
+
     fn as_term<'a>(&self, env: Env<'a>) -> Term<'a> {
         unsafe { Term::new(env, resource::make_resource(env.as_c_arg(), self.raw)) }
     }
@@ -218,9 +571,13 @@ where
     }
 }
 
+fn maybe_env(env: Option<Env>) -> NIF_ENV {
+    env.map_or(ptr::null_mut(), |env| env.as_c_arg())
+}
+
 impl<T> Deref for ResourceArc<T>
 where
-    T: ResourceTypeProvider,
+    T: Send + Sync + 'static,
 {
     type Target = T;
 
@@ -231,7 +588,7 @@ where
 
 impl<T> Clone for ResourceArc<T>
 where
-    T: ResourceTypeProvider,
+    T: Send + Sync + 'static,
 {
     /// Cloning a `ResourceArc` simply increments the reference count for the
     /// resource. The `T` value is not cloned.
@@ -248,7 +605,7 @@ where
 
 impl<T> Drop for ResourceArc<T>
 where
-    T: ResourceTypeProvider,
+    T: Send + Sync + 'static,
 {
     /// When a `ResourceArc` is dropped, the reference count is decremented. If
     /// there are no other references to the resource, the `T` value is dropped.
@@ -270,31 +627,19 @@ macro_rules! resource_struct_init {
 }
 
 #[macro_export]
+#[deprecated(
+    since = "0.33.0",
+    note = "Please implement `rustler::Resource` and use `Env::register` instead."
+)]
 macro_rules! resource {
-    ($struct_name:ty, $env: ident) => {
-        {
-            static mut STRUCT_TYPE: Option<$crate::resource::ResourceType<$struct_name>> = None;
-
-            let temp_struct_type =
-                match $crate::resource::open_struct_resource_type::<$struct_name>(
-                    $env,
-                    concat!(stringify!($struct_name), "\x00"),
-                    $crate::resource::NIF_RESOURCE_FLAGS::ERL_NIF_RT_CREATE
-                    ) {
-                    Some(inner) => inner,
-                    None => {
-                        println!("Failure in creating resource type");
-                        return false;
-                    }
-                };
-            unsafe { STRUCT_TYPE = Some(temp_struct_type) };
-
-            impl $crate::resource::ResourceTypeProvider for $struct_name {
-                fn get_type() -> &'static $crate::resource::ResourceType<Self> {
-                    unsafe { &STRUCT_TYPE }.as_ref()
-                        .expect("The resource type hasn't been initialized. Did you remember to call the function where you used the `resource!` macro?")
-                }
-            }
+    ($struct_name:ty, $env: ident) => {{
+        if let Err(err) = $crate::resource::open_plain_resource_type::<$struct_name>(
+            $env,
+            concat!(stringify!($struct_name), "\x00"),
+            $crate::resource::NIF_RESOURCE_FLAGS::ERL_NIF_RT_CREATE,
+        ) {
+            $crate::codegen_runtime::report_load_error($env, &err.to_string());
+            return false;
         }
-    }
+    }};
 }
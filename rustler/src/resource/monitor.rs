@@ -0,0 +1,69 @@
+use std::cmp::Ordering;
+
+use crate::wrapper::ErlNifMonitor;
+#[cfg(feature = "nif_version_2_15")]
+use crate::{Encoder, Env, Term};
+
+/// A process monitor created by [`ResourceArc::monitor`](super::ResourceArc::monitor).
+///
+/// When the monitored process exits, the `down` callback of the resource's [`Resource`]
+/// implementation is called with the same `Monitor` value, so it can be compared with the one
+/// returned from `monitor` to find out which monitor fired.
+///
+/// [`Resource`]: super::Resource
+#[derive(Copy, Clone)]
+pub struct Monitor {
+    inner: ErlNifMonitor,
+}
+
+impl Monitor {
+    pub fn as_c_arg(&self) -> &ErlNifMonitor {
+        &self.inner
+    }
+
+    pub fn from_c_arg(erl_nif_monitor: ErlNifMonitor) -> Self {
+        Monitor {
+            inner: erl_nif_monitor,
+        }
+    }
+
+    /// Create a term that identifies this monitor.
+    ///
+    /// The returned term can be compared with the monitor references handed out by
+    /// `erlang:monitor/2`, but is otherwise opaque.
+    #[cfg(feature = "nif_version_2_15")]
+    pub fn to_term<'a>(&self, env: Env<'a>) -> Term<'a> {
+        unsafe {
+            Term::new(
+                env,
+                rustler_sys::enif_make_monitor_term(env.as_c_arg(), &self.inner),
+            )
+        }
+    }
+}
+
+#[cfg(feature = "nif_version_2_15")]
+impl Encoder for Monitor {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.to_term(env)
+    }
+}
+
+impl PartialEq for Monitor {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Monitor {}
+
+impl Ord for Monitor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ord = unsafe { rustler_sys::enif_compare_monitors(&self.inner, &other.inner) };
+        ord.cmp(&0)
+    }
+}
+impl PartialOrd for Monitor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
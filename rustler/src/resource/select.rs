@@ -0,0 +1,136 @@
+use super::{Resource, ResourceArc};
+use crate::wrapper::c_int;
+use crate::{Encoder, Env, LocalPid};
+
+/// An OS event that can be watched with [`ResourceArc::select`].
+///
+/// This is a file descriptor on Unix systems (`AsRawFd::as_raw_fd`) and a handle on Windows
+/// (`AsRawHandle::as_raw_handle`).
+pub type Event = rustler_sys::ErlNifEvent;
+
+/// What [`ResourceArc::select`] should do with an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectMode {
+    /// Send `{select, Obj, Ref, ready_input}` once the event is ready for reading.
+    Read,
+    /// Send `{select, Obj, Ref, ready_output}` once the event is ready for writing.
+    Write,
+    /// Combination of `Read` and `Write`.
+    ReadWrite,
+    /// Cancel a pending `Read` selection.
+    #[cfg(feature = "nif_version_2_15")]
+    CancelRead,
+    /// Cancel a pending `Write` selection.
+    #[cfg(feature = "nif_version_2_15")]
+    CancelWrite,
+    /// Cancel all selections on the event and call `Resource::stop` once it is safe to close the
+    /// event.
+    Stop,
+}
+
+impl SelectMode {
+    fn as_flags(self) -> rustler_sys::ErlNifSelectFlags {
+        #[cfg(feature = "nif_version_2_15")]
+        use rustler_sys::ERL_NIF_SELECT_CANCEL;
+        use rustler_sys::{ERL_NIF_SELECT_READ, ERL_NIF_SELECT_STOP, ERL_NIF_SELECT_WRITE};
+
+        match self {
+            SelectMode::Read => ERL_NIF_SELECT_READ,
+            SelectMode::Write => ERL_NIF_SELECT_WRITE,
+            SelectMode::ReadWrite => ERL_NIF_SELECT_READ | ERL_NIF_SELECT_WRITE,
+            #[cfg(feature = "nif_version_2_15")]
+            SelectMode::CancelRead => ERL_NIF_SELECT_CANCEL | ERL_NIF_SELECT_READ,
+            #[cfg(feature = "nif_version_2_15")]
+            SelectMode::CancelWrite => ERL_NIF_SELECT_CANCEL | ERL_NIF_SELECT_WRITE,
+            SelectMode::Stop => ERL_NIF_SELECT_STOP,
+        }
+    }
+}
+
+/// The successful result of a [`ResourceArc::select`] call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelectStatus(c_int);
+
+impl SelectStatus {
+    /// The `stop` callback was called directly by `select`.
+    pub fn stop_called(self) -> bool {
+        self.0 & rustler_sys::ERL_NIF_SELECT_STOP_CALLED != 0
+    }
+
+    /// The `stop` callback is scheduled to run on another thread or later by this thread.
+    pub fn stop_scheduled(self) -> bool {
+        self.0 & rustler_sys::ERL_NIF_SELECT_STOP_SCHEDULED != 0
+    }
+
+    /// A pending read selection was cancelled before its message was sent.
+    #[cfg(feature = "nif_version_2_15")]
+    pub fn read_cancelled(self) -> bool {
+        self.0 & rustler_sys::ERL_NIF_SELECT_READ_CANCELLED != 0
+    }
+
+    /// A pending write selection was cancelled before its message was sent.
+    #[cfg(feature = "nif_version_2_15")]
+    pub fn write_cancelled(self) -> bool {
+        self.0 & rustler_sys::ERL_NIF_SELECT_WRITE_CANCELLED != 0
+    }
+}
+
+/// Returned when a [`ResourceArc::select`] call failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectError {
+    /// The event is not a valid OS event object.
+    InvalidEvent,
+    /// The system call to add the event object to the poll set failed.
+    Failed,
+    /// The operation is not supported on this platform.
+    NotSupported,
+}
+
+impl<T> ResourceArc<T>
+where
+    T: Resource,
+{
+    /// Watch `event` for readiness on behalf of this resource, see
+    /// [enif\_select](https://www.erlang.org/doc/man/erl_nif.html#enif_select).
+    ///
+    /// Once the event is ready, a message `{select, Resource, Ref, ready_input | ready_output}`
+    /// is sent to `pid`, or to the calling process if `pid` is `None`. `reference` has to be a
+    /// reference or the atom `undefined`. The selection is a one-shot: after a message has been
+    /// sent, `select` has to be called again to wait for the next readiness notification.
+    ///
+    /// Before closing the event, it has to be deselected with `SelectMode::Stop`. The event may
+    /// only be closed once `Resource::stop` was called for it, which requires `T::IMPLEMENTS_STOP`
+    /// to be set.
+    pub fn select(
+        &self,
+        env: Env,
+        event: Event,
+        mode: SelectMode,
+        pid: Option<&LocalPid>,
+        reference: impl Encoder,
+    ) -> Result<SelectStatus, SelectError> {
+        let pid = pid.map_or(std::ptr::null(), |pid| pid.as_c_arg());
+        let reference = reference.encode(env);
+
+        let res = unsafe {
+            rustler_sys::enif_select(
+                env.as_c_arg(),
+                event,
+                mode.as_flags(),
+                self.raw,
+                pid,
+                reference.as_c_arg(),
+            )
+        };
+
+        if res >= 0 {
+            Ok(SelectStatus(res))
+        } else if res & rustler_sys::ERL_NIF_SELECT_INVALID_EVENT != 0 {
+            Err(SelectError::InvalidEvent)
+        } else if res & rustler_sys::ERL_NIF_SELECT_NOTSUP != 0 {
+            Err(SelectError::NotSupported)
+        } else {
+            Err(SelectError::Failed)
+        }
+    }
+}
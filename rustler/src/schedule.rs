@@ -1,13 +1,111 @@
+use std::any::Any;
+use std::sync::Mutex;
+
 use crate::wrapper::ErlNifTaskFlags;
-use crate::Env;
+use crate::{Env, Resource};
+
+mod fold;
+pub use fold::{fold_list, fold_map, ListFold, MapFold};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SchedulerFlags {
     Normal = ErlNifTaskFlags::ERL_NIF_NORMAL_JOB as isize,
     DirtyCpu = ErlNifTaskFlags::ERL_NIF_DIRTY_JOB_CPU_BOUND as isize,
     DirtyIo = ErlNifTaskFlags::ERL_NIF_DIRTY_JOB_IO_BOUND as isize,
 }
 
+/// The kind of scheduler the current thread belongs to, or `None` if it is not a scheduler
+/// thread.
+pub fn current_scheduler() -> Option<SchedulerFlags> {
+    match unsafe { rustler_sys::enif_thread_type() } {
+        rustler_sys::ERL_NIF_THR_NORMAL_SCHEDULER => Some(SchedulerFlags::Normal),
+        rustler_sys::ERL_NIF_THR_DIRTY_CPU_SCHEDULER => Some(SchedulerFlags::DirtyCpu),
+        rustler_sys::ERL_NIF_THR_DIRTY_IO_SCHEDULER => Some(SchedulerFlags::DirtyIo),
+        _ => None,
+    }
+}
+
 pub fn consume_timeslice(env: Env, percent: i32) -> bool {
     let success = unsafe { rustler_sys::enif_consume_timeslice(env.as_c_arg(), percent) };
     success == 1
 }
+
+/// The result of a step of a yielding NIF, see `#[rustler::nif(yielding)]`.
+///
+/// `Yield(state)` gives control back to the scheduler and calls the NIF again later with `state`,
+/// using `enif_schedule_nif`. `Reschedule(scheduler, state)` does the same, but continues on the
+/// given scheduler, e.g. to move large inputs to a dirty scheduler. `Done(value)` returns `value`
+/// to the caller.
+///
+/// ```ignore
+/// #[rustler::nif(yielding)]
+/// fn sum(env: Env, n: u64, state: Option<(u64, u64)>) -> Yielding<(u64, u64), u64> {
+///     let (mut i, mut acc) = state.unwrap_or((0, 0));
+///     while i < n {
+///         acc += i;
+///         i += 1;
+///         if i % 1000 == 0 && consume_timeslice(env, 1) {
+///             return Yield((i, acc));
+///         }
+///     }
+///     Done(acc)
+/// }
+/// ```
+///
+/// ```ignore
+/// #[rustler::nif(yielding)]
+/// fn checksum(data: Binary, state: Option<()>) -> Yielding<(), u32> {
+///     if state.is_none() && data.len() > 1_000_000 {
+///         return Reschedule(SchedulerFlags::DirtyCpu, ());
+///     }
+///     Done(crc32(&data))
+/// }
+/// ```
+///
+/// The state argument must be `Option<S>` for the `S` of the returned `Yielding<S, T>`, which is
+/// checked at compile time:
+///
+/// ```compile_fail,E0308
+/// use rustler::schedule::{Done, Yielding};
+///
+/// #[rustler::nif(yielding)]
+/// fn mismatched(state: Option<u32>) -> Yielding<u64, u64> {
+///     Done(state.unwrap_or(0) as u64)
+/// }
+/// ```
+pub enum Yielding<S, T> {
+    /// Yield to the scheduler and continue later on the same kind of scheduler with the given
+    /// state.
+    Yield(S),
+    /// Continue on the given scheduler with the given state.
+    Reschedule(SchedulerFlags, S),
+    /// Return the given value from the NIF.
+    Done(T),
+}
+
+pub use self::Yielding::{Done, Reschedule, Yield};
+
+/// Resource type holding the state of a yielding NIF between two calls.
+#[doc(hidden)]
+pub struct YieldState(Mutex<Option<Box<dyn Any + Send>>>);
+
+impl YieldState {
+    pub(crate) fn new<S: Send + 'static>(state: S) -> Self {
+        YieldState(Mutex::new(Some(Box::new(state))))
+    }
+
+    pub(crate) fn put<S: Send + 'static>(&self, state: S) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(state));
+    }
+
+    pub(crate) fn take<S: 'static>(&self) -> Option<S> {
+        let state = self
+            .0
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take()?;
+        state.downcast().ok().map(|state| *state)
+    }
+}
+
+impl Resource for YieldState {}
@@ -0,0 +1,492 @@
+//! Folding over large lists and maps without blocking the scheduler.
+
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use super::{consume_timeslice, current_scheduler, SchedulerFlags, YieldState};
+use crate::codegen_runtime::{handle_nif_result, NifReturnable, NifReturned};
+use crate::wrapper::{c_int, NIF_ENV, NIF_TERM};
+use crate::{Encoder, Env, Error, MapIterator, NifResult, ResourceArc, Term};
+
+/// Number of elements processed between two checks of the timeslice.
+const CHECK_INTERVAL: usize = 64;
+
+/// Reports the time spent to the VM, see `enif_consume_timeslice`.
+struct Timeslice {
+    since: Instant,
+    count: usize,
+}
+
+impl Timeslice {
+    fn new() -> Self {
+        Timeslice {
+            since: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Count one processed element, and return `true` if the timeslice is used up.
+    fn tick(&mut self, env: Env) -> bool {
+        self.count += 1;
+        if self.count % CHECK_INTERVAL != 0 {
+            return false;
+        }
+
+        // A timeslice is about one millisecond.
+        let elapsed = self.since.elapsed().as_micros();
+        let percent = (elapsed / 10).clamp(1, 100) as i32;
+        self.since = Instant::now();
+        consume_timeslice(env, percent)
+    }
+}
+
+fn no_finish<A>(acc: A) -> A {
+    acc
+}
+
+fn reschedule(
+    env: Env,
+    fun_name: &str,
+    fun: unsafe extern "C" fn(NIF_ENV, c_int, *const NIF_TERM) -> NIF_TERM,
+    position: Term,
+    state: ResourceArc<YieldState>,
+) -> NifReturned {
+    NifReturned::Reschedule {
+        fun_name: CString::new(fun_name).unwrap(),
+        flags: current_scheduler().unwrap_or(SchedulerFlags::Normal),
+        fun,
+        args: vec![position.as_c_arg(), state.encode(env).as_c_arg()],
+    }
+}
+
+/// Decode the arguments of a rescheduled fold: the position and the resource with the state.
+unsafe fn resume<'a, S: Send + 'static>(
+    env: Env<'a>,
+    argc: c_int,
+    argv: *const NIF_TERM,
+) -> Option<(Term<'a>, S, ResourceArc<YieldState>)> {
+    let args = std::slice::from_raw_parts(argv, argc as usize);
+    if args.len() != 2 {
+        return None;
+    }
+
+    let position = Term::new(env, args[0]);
+    let resource: ResourceArc<YieldState> = Term::new(env, args[1]).decode().ok()?;
+    let state = resource.take()?;
+    Some((position, state, resource))
+}
+
+/// The return value of a NIF that folds over a list, see `fold_list()`.
+pub struct ListFold<'a, A, F, D = fn(A) -> A> {
+    list: Term<'a>,
+    acc: A,
+    step: F,
+    finish: D,
+    resource: Option<ResourceArc<YieldState>>,
+}
+
+/// Fold over `list` with `step`, starting from `init`, without blocking the scheduler.
+///
+/// Returning the `ListFold` from a NIF runs the fold. Whenever the timeslice of the NIF is used
+/// up, the position in the list and the accumulator are saved and the fold continues in a new
+/// call scheduled with `enif_schedule_nif`. When the end of the list is reached, the accumulator
+/// is returned from the NIF, or passed to the function given to `ListFold::finish()`.
+///
+/// Raises `badarg` if `list` is not a proper list, and the error of `step` if it fails.
+///
+/// The return type of the NIF has to name `ListFold` (not an alias of it), which tells
+/// `rustler::init!` to register the resource type that keeps the state between calls.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn sum(list: Term) -> ListFold<i64, impl FnMut(i64, Term) -> NifResult<i64>> {
+///     fold_list(list, 0, |acc, term| Ok(acc + term.decode::<i64>()?))
+/// }
+/// ```
+pub fn fold_list<'a, A, F>(list: Term<'a>, init: A, step: F) -> ListFold<'a, A, F>
+where
+    A: Send + 'static,
+    F: for<'b> FnMut(A, Term<'b>) -> NifResult<A> + Send + 'static,
+{
+    ListFold {
+        list,
+        acc: init,
+        step,
+        finish: no_finish,
+        resource: None,
+    }
+}
+
+impl<'a, A, F, D> ListFold<'a, A, F, D> {
+    /// Compute the return value of the NIF from the final accumulator with `finish`.
+    pub fn finish<E, R>(self, finish: E) -> ListFold<'a, A, F, E>
+    where
+        E: FnOnce(A) -> R + Send + 'static,
+        R: NifReturnable,
+    {
+        ListFold {
+            list: self.list,
+            acc: self.acc,
+            step: self.step,
+            finish,
+            resource: self.resource,
+        }
+    }
+}
+
+impl<'a, A, F, D, R> ListFold<'a, A, F, D>
+where
+    A: Send + 'static,
+    F: for<'b> FnMut(A, Term<'b>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    unsafe fn run(self, env: Env) -> NifReturned {
+        let ListFold {
+            list: mut tail,
+            mut acc,
+            mut step,
+            finish,
+            resource,
+        } = self;
+        let mut timeslice = Timeslice::new();
+
+        loop {
+            match tail.list_get_cell() {
+                Ok((head, rest)) => {
+                    acc = match step(acc, head) {
+                        Ok(acc) => acc,
+                        Err(err) => return err.into_returned(env),
+                    };
+                    tail = rest;
+                }
+                Err(_) if tail.is_empty_list() => return finish(acc).into_returned(env),
+                Err(err) => return err.into_returned(env),
+            }
+
+            if timeslice.tick(env) {
+                return ListFold {
+                    list: tail,
+                    acc,
+                    step,
+                    finish,
+                    resource,
+                }
+                .reschedule(env);
+            }
+        }
+    }
+
+    /// Save the state and continue the fold over `self.list` in a new call.
+    fn reschedule(self, env: Env) -> NifReturned {
+        let state = (self.acc, self.step, self.finish);
+        let resource = match self.resource {
+            Some(resource) => {
+                resource.put(state);
+                resource
+            }
+            None => ResourceArc::new(YieldState::new(state)),
+        };
+        reschedule(
+            env,
+            "rustler_fold_list",
+            list_fold_step::<A, F, D, R>,
+            self.list,
+            resource,
+        )
+    }
+}
+
+unsafe extern "C" fn list_fold_step<A, F, D, R>(
+    nif_env: NIF_ENV,
+    argc: c_int,
+    argv: *const NIF_TERM,
+) -> NIF_TERM
+where
+    A: Send + 'static,
+    F: for<'b> FnMut(A, Term<'b>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    let lifetime = ();
+    let env = Env::new(&lifetime, nif_env);
+
+    let returned = match resume::<(A, F, D)>(env, argc, argv) {
+        Some((list, (acc, step, finish), resource)) => ListFold {
+            list,
+            acc,
+            step,
+            finish,
+            resource: Some(resource),
+        }
+        .into_returned(env),
+        None => NifReturned::BadArg,
+    };
+    returned.apply(env)
+}
+
+unsafe impl<'a, A, F, D, R> NifReturnable for ListFold<'a, A, F, D>
+where
+    A: Send + 'static,
+    F: for<'b> FnMut(A, Term<'b>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.run(env))) {
+            Ok(returned) => returned,
+            Err(err) => handle_nif_result::<R>(Err(err), env),
+        }
+    }
+}
+
+/// The return value of a NIF that folds over a map, see `fold_map()`.
+pub struct MapFold<'a, A, F, D = fn(A) -> A> {
+    map: Term<'a>,
+    acc: A,
+    step: F,
+    finish: D,
+}
+
+/// Fold over the entries of `map` with `step`, starting from `init`, without blocking the
+/// scheduler.
+///
+/// This works like `fold_list()`. Since a map iterator cannot be kept across NIF calls, the
+/// entries not processed yet are collected into a list of `{key, value}` tuples once the
+/// timeslice of the first call is used up, and the fold continues over that list. Collecting the
+/// entries is spread over as many calls as needed. Each of them has to move a new iterator past
+/// the entries handled before, which is much cheaper than collecting them, after which every
+/// call of the fold resumes right where the previous one stopped.
+///
+/// Raises `badarg` if `map` is not a map, and the error of `step` if it fails. As with
+/// `fold_list()`, the return type of the NIF has to name `MapFold`.
+pub fn fold_map<'a, A, F>(map: Term<'a>, init: A, step: F) -> MapFold<'a, A, F>
+where
+    A: Send + 'static,
+    F: for<'b, 'c> FnMut(A, Term<'b>, Term<'c>) -> NifResult<A> + Send + 'static,
+{
+    MapFold {
+        map,
+        acc: init,
+        step,
+        finish: no_finish,
+    }
+}
+
+impl<'a, A, F, D> MapFold<'a, A, F, D> {
+    /// Compute the return value of the NIF from the final accumulator with `finish`.
+    pub fn finish<E, R>(self, finish: E) -> MapFold<'a, A, F, E>
+    where
+        E: FnOnce(A) -> R + Send + 'static,
+        R: NifReturnable,
+    {
+        MapFold {
+            map: self.map,
+            acc: self.acc,
+            step: self.step,
+            finish,
+        }
+    }
+}
+
+/// Adapt the `step` of a map fold to the `{key, value}` tuples of the remaining entries.
+fn entry_step<A, F>(mut step: F) -> impl for<'b> FnMut(A, Term<'b>) -> NifResult<A> + Send + 'static
+where
+    A: Send + 'static,
+    F: for<'b, 'c> FnMut(A, Term<'b>, Term<'c>) -> NifResult<A> + Send + 'static,
+{
+    move |acc: A, entry: Term| {
+        let (key, value): (Term, Term) = entry.decode()?;
+        step(acc, key, value)
+    }
+}
+
+impl<'a, A, F, D, R> MapFold<'a, A, F, D>
+where
+    A: Send + 'static,
+    F: for<'b, 'c> FnMut(A, Term<'b>, Term<'c>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    unsafe fn run(self, env: Env) -> NifReturned {
+        let MapFold {
+            map,
+            mut acc,
+            mut step,
+            finish,
+        } = self;
+
+        let iter = match MapIterator::new(map) {
+            Some(iter) => iter,
+            None => return Error::BadArg.into_returned(env),
+        };
+        let mut timeslice = Timeslice::new();
+
+        for (index, (key, value)) in iter.enumerate() {
+            acc = match step(acc, key, value) {
+                Ok(acc) => acc,
+                Err(err) => return err.into_returned(env),
+            };
+
+            if timeslice.tick(env) {
+                return MapCollect {
+                    map,
+                    position: index + 1,
+                    collected: Term::list_new_empty(map.get_env()),
+                    acc,
+                    step,
+                    finish,
+                    resource: None,
+                }
+                .reschedule(env);
+            }
+        }
+
+        finish(acc).into_returned(env)
+    }
+}
+
+/// A map fold that collects the entries it has not processed yet into a list of `{key, value}`
+/// tuples, see `fold_map()`.
+struct MapCollect<'a, A, F, D> {
+    map: Term<'a>,
+    /// The number of entries processed or collected so far, which come first when iterating
+    /// over `map`.
+    position: usize,
+    collected: Term<'a>,
+    acc: A,
+    step: F,
+    finish: D,
+    resource: Option<ResourceArc<YieldState>>,
+}
+
+impl<'a, A, F, D, R> MapCollect<'a, A, F, D>
+where
+    A: Send + 'static,
+    F: for<'b, 'c> FnMut(A, Term<'b>, Term<'c>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    unsafe fn run(self, env: Env) -> NifReturned {
+        let MapCollect {
+            map,
+            mut position,
+            mut collected,
+            acc,
+            step,
+            finish,
+            resource,
+        } = self;
+
+        let mut iter = match MapIterator::new(map) {
+            Some(iter) => iter,
+            None => return Error::BadArg.into_returned(env),
+        };
+        for _ in iter.by_ref().take(position) {}
+        // Moving past the entries handled before is left out of the reported time, so that every
+        // call collects as many entries as its timeslice allows.
+        let mut timeslice = Timeslice::new();
+
+        for entry in iter {
+            collected = collected.list_prepend(entry);
+            position += 1;
+
+            if timeslice.tick(env) {
+                return MapCollect {
+                    map,
+                    position,
+                    collected,
+                    acc,
+                    step,
+                    finish,
+                    resource,
+                }
+                .reschedule(env);
+            }
+        }
+
+        ListFold {
+            list: collected,
+            acc,
+            step: entry_step(step),
+            finish,
+            resource,
+        }
+        .run(env)
+    }
+
+    /// Save the state and continue collecting the entries of `self.map` in a new call.
+    fn reschedule(self, env: Env) -> NifReturned {
+        let state = (self.acc, self.step, self.finish);
+        let resource = match self.resource {
+            Some(resource) => {
+                resource.put(state);
+                resource
+            }
+            None => ResourceArc::new(YieldState::new(state)),
+        };
+        let position = (self.map, self.position, self.collected).encode(env);
+        reschedule(
+            env,
+            "rustler_fold_map",
+            map_collect_step::<A, F, D, R>,
+            position,
+            resource,
+        )
+    }
+}
+
+unsafe extern "C" fn map_collect_step<A, F, D, R>(
+    nif_env: NIF_ENV,
+    argc: c_int,
+    argv: *const NIF_TERM,
+) -> NIF_TERM
+where
+    A: Send + 'static,
+    F: for<'b, 'c> FnMut(A, Term<'b>, Term<'c>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    let lifetime = ();
+    let env = Env::new(&lifetime, nif_env);
+
+    let resumed = resume::<(A, F, D)>(env, argc, argv).and_then(|(position, state, resource)| {
+        let (map, position, collected): (Term, usize, Term) = position.decode().ok()?;
+        Some((map, position, collected, state, resource))
+    });
+    let returned = match resumed {
+        Some((map, position, collected, (acc, step, finish), resource)) => {
+            let collect = MapCollect {
+                map,
+                position,
+                collected,
+                acc,
+                step,
+                finish,
+                resource: Some(resource),
+            };
+            match panic::catch_unwind(AssertUnwindSafe(|| collect.run(env))) {
+                Ok(returned) => returned,
+                Err(err) => handle_nif_result::<R>(Err(err), env),
+            }
+        }
+        None => NifReturned::BadArg,
+    };
+    returned.apply(env)
+}
+
+unsafe impl<'a, A, F, D, R> NifReturnable for MapFold<'a, A, F, D>
+where
+    A: Send + 'static,
+    F: for<'b, 'c> FnMut(A, Term<'b>, Term<'c>) -> NifResult<A> + Send + 'static,
+    D: FnOnce(A) -> R + Send + 'static,
+    R: NifReturnable,
+{
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.run(env))) {
+            Ok(returned) => returned,
+            Err(err) => handle_nif_result::<R>(Err(err), env),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Synchronization primitives backed by the Erlang VM.
+//!
+//! These work like their counterparts in `std::sync`, but are created with `enif_mutex_create`,
+//! `enif_rwlock_create` and `enif_cond_create`. The VM knows about them by name, so they show up
+//! in lock counting (`lcnt`) and other lock debugging tools.
+//!
+//! Unlike `std::sync`, the locks are not poisoned if a thread panics while holding them.
+
+use std::cell::UnsafeCell;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use rustler_sys::{ErlNifCond, ErlNifMutex, ErlNifRWLock};
+
+fn c_name(name: &str) -> CString {
+    CString::new(name).expect("lock name must not contain a nul byte")
+}
+
+unsafe fn name_from_c<'a>(name: *const rustler_sys::c_char) -> &'a str {
+    if name.is_null() {
+        ""
+    } else {
+        CStr::from_ptr(name).to_str().unwrap_or("")
+    }
+}
+
+/// A mutual exclusion lock, see `std::sync::Mutex`.
+pub struct Mutex<T: ?Sized> {
+    inner: *mut ErlNifMutex,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Create a new mutex called `name` that protects `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a nul byte or if the VM fails to create the mutex.
+    pub fn new(name: &str, value: T) -> Self {
+        let name = c_name(name);
+        let inner = unsafe { rustler_sys::enif_mutex_create(name.as_ptr() as *mut _) };
+        assert!(!inner.is_null(), "enif_mutex_create failed");
+        Mutex {
+            inner,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consume the mutex and return the protected value.
+    pub fn into_inner(self) -> T {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe {
+            rustler_sys::enif_mutex_destroy(this.inner);
+            std::ptr::read(&this.data).into_inner()
+        }
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Block the current thread until the lock is acquired.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        unsafe { rustler_sys::enif_mutex_lock(self.inner) };
+        MutexGuard {
+            mutex: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Acquire the lock if it is free, otherwise return `None`.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        if unsafe { rustler_sys::enif_mutex_trylock(self.inner) } == 0 {
+            Some(MutexGuard {
+                mutex: self,
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Return a mutable reference to the protected value.
+    ///
+    /// No locking is needed since the borrow checker guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// The name given to the mutex on creation.
+    pub fn name(&self) -> &str {
+        unsafe { name_from_c(rustler_sys::enif_mutex_name(self.inner)) }
+    }
+}
+
+impl<T: ?Sized> Drop for Mutex<T> {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_mutex_destroy(self.inner) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mutex")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// RAII guard for a locked `Mutex`. The lock is released when the guard is dropped.
+pub struct MutexGuard<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    // The lock must be released on the thread that acquired it.
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<'a, T: ?Sized + Sync> Sync for MutexGuard<'a, T> {}
+
+impl<'a, T: ?Sized> Deref for MutexGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for MutexGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for MutexGuard<'a, T> {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_mutex_unlock(self.mutex.inner) }
+    }
+}
+
+/// A reader-writer lock, see `std::sync::RwLock`.
+pub struct RwLock<T: ?Sized> {
+    inner: *mut ErlNifRWLock,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    /// Create a new reader-writer lock called `name` that protects `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a nul byte or if the VM fails to create the lock.
+    pub fn new(name: &str, value: T) -> Self {
+        let name = c_name(name);
+        let inner = unsafe { rustler_sys::enif_rwlock_create(name.as_ptr() as *mut _) };
+        assert!(!inner.is_null(), "enif_rwlock_create failed");
+        RwLock {
+            inner,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consume the lock and return the protected value.
+    pub fn into_inner(self) -> T {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe {
+            rustler_sys::enif_rwlock_destroy(this.inner);
+            std::ptr::read(&this.data).into_inner()
+        }
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Block the current thread until shared read access is acquired.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        unsafe { rustler_sys::enif_rwlock_rlock(self.inner) };
+        RwLockReadGuard {
+            lock: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Acquire shared read access if possible, otherwise return `None`.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        if unsafe { rustler_sys::enif_rwlock_tryrlock(self.inner) } == 0 {
+            Some(RwLockReadGuard {
+                lock: self,
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Block the current thread until exclusive write access is acquired.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        unsafe { rustler_sys::enif_rwlock_rwlock(self.inner) };
+        RwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Acquire exclusive write access if possible, otherwise return `None`.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        if unsafe { rustler_sys::enif_rwlock_tryrwlock(self.inner) } == 0 {
+            Some(RwLockWriteGuard {
+                lock: self,
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Return a mutable reference to the protected value.
+    ///
+    /// No locking is needed since the borrow checker guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// The name given to the lock on creation.
+    pub fn name(&self) -> &str {
+        unsafe { name_from_c(rustler_sys::enif_rwlock_name(self.inner)) }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLock<T> {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_rwlock_destroy(self.inner) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RwLock")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// RAII guard for shared read access to a `RwLock`.
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<'a, T: ?Sized + Sync> Sync for RwLockReadGuard<'a, T> {}
+
+impl<'a, T: ?Sized> Deref for RwLockReadGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockReadGuard<'a, T> {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_rwlock_runlock(self.lock.inner) }
+    }
+}
+
+/// RAII guard for exclusive write access to a `RwLock`.
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<'a, T: ?Sized + Sync> Sync for RwLockWriteGuard<'a, T> {}
+
+impl<'a, T: ?Sized> Deref for RwLockWriteGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for RwLockWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_rwlock_rwunlock(self.lock.inner) }
+    }
+}
+
+/// A condition variable, see `std::sync::Condvar`.
+///
+/// Waiting on a condition variable blocks the calling thread, so avoid doing it on a normal
+/// scheduler thread.
+pub struct Condvar {
+    inner: *mut ErlNifCond,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    /// Create a new condition variable called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a nul byte or if the VM fails to create the condition variable.
+    pub fn new(name: &str) -> Self {
+        let name = c_name(name);
+        let inner = unsafe { rustler_sys::enif_cond_create(name.as_ptr() as *mut _) };
+        assert!(!inner.is_null(), "enif_cond_create failed");
+        Condvar { inner }
+    }
+
+    /// Atomically release the lock held by `guard` and block until this condition variable is
+    /// notified. The lock is acquired again before returning.
+    ///
+    /// Like with `std::sync::Condvar`, spurious wakeups are possible, so check the condition
+    /// again after waking up, or use `wait_while`.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        unsafe { rustler_sys::enif_cond_wait(self.inner, guard.mutex.inner) };
+        guard
+    }
+
+    /// Block while `condition` returns `true` for the protected value.
+    pub fn wait_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// Wake up one thread blocked on this condition variable.
+    pub fn notify_one(&self) {
+        unsafe { rustler_sys::enif_cond_signal(self.inner) }
+    }
+
+    /// Wake up all threads blocked on this condition variable.
+    pub fn notify_all(&self) {
+        unsafe { rustler_sys::enif_cond_broadcast(self.inner) }
+    }
+
+    /// The name given to the condition variable on creation.
+    pub fn name(&self) -> &str {
+        unsafe { name_from_c(rustler_sys::enif_cond_name(self.inner)) }
+    }
+}
+
+impl Drop for Condvar {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_cond_destroy(self.inner) }
+    }
+}
+
+impl fmt::Debug for Condvar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condvar")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,94 @@
+//! Information about the Erlang VM running the NIF.
+
+use std::ffi::{CStr, CString};
+use std::mem::{self, MaybeUninit};
+
+use rustler_sys::{c_char, ErlNifSysInfo};
+
+/// Information about the running VM, see `system_info()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SystemInfo {
+    /// The version of the runtime system, e.g. `"14.2"`.
+    pub erts_version: String,
+    /// The OTP release, e.g. `"26"`.
+    pub otp_release: String,
+    /// The major and minor version of the driver API.
+    pub driver_version: (u32, u32),
+    /// The major and minor version of the NIF API.
+    pub nif_version: (u32, u32),
+    /// Whether the runtime system has thread support.
+    pub thread_support: bool,
+    /// Whether the runtime system has SMP support.
+    pub smp_support: bool,
+    /// The number of async threads.
+    pub async_threads: usize,
+    /// The number of (normal) scheduler threads, like `erlang:system_info(schedulers)`.
+    pub scheduler_threads: usize,
+    /// Whether dirty schedulers are enabled.
+    pub dirty_scheduler_support: bool,
+}
+
+unsafe fn c_string(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }
+}
+
+/// Information about the running VM, see
+/// [enif_system_info](https://www.erlang.org/doc/man/erl_nif.html#enif_system_info).
+///
+/// This can be used to size thread pools after the number of schedulers, or to check that dirty
+/// schedulers are available.
+pub fn system_info() -> SystemInfo {
+    let info = unsafe {
+        let mut info = MaybeUninit::<ErlNifSysInfo>::zeroed();
+        rustler_sys::enif_system_info(info.as_mut_ptr(), mem::size_of::<ErlNifSysInfo>());
+        info.assume_init()
+    };
+
+    unsafe {
+        SystemInfo {
+            erts_version: c_string(info.erts_version),
+            otp_release: c_string(info.otp_release),
+            driver_version: (
+                info.driver_major_version as u32,
+                info.driver_minor_version as u32,
+            ),
+            nif_version: (info.nif_major_version as u32, info.nif_minor_version as u32),
+            thread_support: info.thread_support != 0,
+            smp_support: info.smp_support != 0,
+            async_threads: info.async_threads as usize,
+            scheduler_threads: info.scheduler_threads as usize,
+            dirty_scheduler_support: info.dirty_scheduler_support != 0,
+        }
+    }
+}
+
+/// Look up the environment variable `key` in the environment of the VM, like `os:getenv/1`.
+///
+/// Unlike `std::env::var`, this sees the changes made with `os:putenv/2` and
+/// `System.put_env/2`. Returns `None` if the variable is not set or `key` contains a NUL byte.
+/// Values that are not valid UTF-8 are converted lossily.
+pub fn getenv(key: &str) -> Option<String> {
+    let key = CString::new(key).ok()?;
+    let mut buffer: Vec<u8> = vec![0; 256];
+
+    loop {
+        let mut size = buffer.len();
+        let result = unsafe {
+            rustler_sys::enif_getenv(key.as_ptr(), buffer.as_mut_ptr() as *mut c_char, &mut size)
+        };
+
+        match result {
+            0 => {
+                buffer.truncate(size);
+                return Some(String::from_utf8_lossy(&buffer).into_owned());
+            }
+            // The buffer is too small, `size` is now the required size.
+            1 => buffer.resize(size.max(buffer.len() + 1), 0),
+            _ => return None,
+        }
+    }
+}
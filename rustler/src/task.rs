@@ -0,0 +1,188 @@
+//! Running futures for NIFs.
+//!
+//! A NIF can return a `Task`, or be declared as an `async fn`, to compute its result with a
+//! future. The NIF call returns a fresh reference right away, while the future is polled off the
+//! scheduler threads by an `Executor`. When the future completes, `{Ref, Result}` is sent to the
+//! calling process. If the future panics, `{Ref, {error, Reason}}` is sent instead, like for
+//! `rustler::thread::spawn()`.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! async fn fetch(url: String) -> String {
+//!     client().get(&url).await
+//! }
+//! ```
+//!
+//! ```elixir
+//! ref = MyNif.fetch("https://example.com")
+//!
+//! receive do
+//!   {^ref, body} -> body
+//! end
+//! ```
+//!
+//! Futures must be `'static`, so async NIFs cannot take `Env`, `Term` or other borrowed arguments.
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+use crate::codegen_runtime::{NifReturnable, NifReturned};
+use crate::thread::{panic_error_tuple, JobSpawner, SpawnError, ThreadSpawner};
+use crate::{Encoder, Env, OwnedEnv, OwnedReference, Reference};
+
+/// Something that can drive futures to completion, e.g. an async runtime.
+///
+/// Implement this to run NIF futures on the runtime of your choice. The executor must poll the
+/// futures on threads that are not managed by the Erlang VM.
+pub trait Executor {
+    /// Run the given future to completion.
+    fn spawn<F: Future<Output = ()> + Send + 'static>(future: F);
+
+    /// Run the given future to completion, unless the executor is at capacity.
+    ///
+    /// This is called on a scheduler thread by `spawn()`, so it must not block. The default
+    /// implementation always accepts the future and delegates to `spawn()`.
+    fn try_spawn<F: Future<Output = ()> + Send + 'static>(future: F) -> Result<(), SpawnError> {
+        Self::spawn(future);
+        Ok(())
+    }
+}
+
+/// A minimal `Executor` that runs each future on a thread of the `JobSpawner` `S`, blocking that
+/// thread until the future completes.
+///
+/// Every pending future occupies a thread of `S`. With a bounded spawner like `PoolSpawner`,
+/// futures are rejected once all workers are busy and the queue is full, and futures that wait
+/// for each other can deadlock the pool if there are more of them than workers.
+pub struct ThreadExecutor<S: JobSpawner = ThreadSpawner>(PhantomData<S>);
+
+impl<S: JobSpawner> Executor for ThreadExecutor<S> {
+    fn spawn<F: Future<Output = ()> + Send + 'static>(future: F) {
+        // The future catches its own panics, see `spawn()`.
+        let future = AssertUnwindSafe(future);
+        S::spawn(move || block_on(future));
+    }
+
+    fn try_spawn<F: Future<Output = ()> + Send + 'static>(future: F) -> Result<(), SpawnError> {
+        let future = AssertUnwindSafe(future);
+        S::try_spawn(move || block_on(future))
+    }
+}
+
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Block the current thread until `future` completes and return its output.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Spawn `future` on the executor `E` and return a reference tagging the reply.
+///
+/// When the future completes, `{Ref, Result}` is sent to the calling process, or `{Ref, {error,
+/// Reason}}` if the future panics. If the executor rejects the future, e.g. because the queue of a
+/// `PoolSpawner` is full, `{Ref, {error, Reason}}` with the `SpawnError` is sent right away.
+pub fn spawn<'a, E, F>(env: Env<'a>, future: F) -> Reference<'a>
+where
+    E: Executor,
+    F: Future + Send + 'static,
+    F::Output: Encoder + Send,
+{
+    let pid = env.pid();
+    let reference = env.make_ref();
+    let owned_reference = OwnedReference::new(reference);
+
+    let result = E::try_spawn(async move {
+        let result = CatchUnwind(Box::pin(future)).await;
+        let _ = OwnedEnv::new().send_and_clear(&pid, |env| {
+            let reply = match result {
+                Ok(output) => output.encode(env),
+                Err(err) => panic_error_tuple(env, &err),
+            };
+            (&owned_reference, reply).encode(env)
+        });
+    });
+
+    if let Err(err) = result {
+        let _ = env.send(&pid, (reference, env.error_tuple(err)));
+    }
+
+    reference
+}
+
+/// Polls the inner future, turning panics into an `Err` with the panic payload.
+struct CatchUnwind<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.0.as_mut();
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+}
+
+/// The return value of a NIF whose result is computed by a future.
+///
+/// Returning a `Task` from a NIF spawns the future on the executor `E` with `spawn()`, and the NIF
+/// returns the reference tagging the reply. `async fn` NIFs return a `Task` with the default
+/// executor.
+pub struct Task<F, E = ThreadExecutor> {
+    future: F,
+    executor: PhantomData<E>,
+}
+
+impl<F> Task<F> {
+    /// Create a task that runs `future` on a `ThreadExecutor`.
+    pub fn new(future: F) -> Self {
+        Task::with_executor(future)
+    }
+}
+
+impl<F, E> Task<F, E> {
+    /// Create a task that runs `future` on the executor `E`.
+    pub fn with_executor(future: F) -> Self {
+        Task {
+            future,
+            executor: PhantomData,
+        }
+    }
+}
+
+unsafe impl<F, E> NifReturnable for Task<F, E>
+where
+    E: Executor,
+    F: Future + Send + 'static,
+    F::Output: Encoder + Send,
+{
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        NifReturned::Term(spawn::<E, F>(env, self.future).as_c_arg())
+    }
+}
@@ -1,8 +1,21 @@
 use crate::env::OwnedEnv;
 use crate::{Atom, Encoder, Env, Term};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::mem;
 use std::panic;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
 use std::thread;
 
+use rustler_sys::{c_int, c_void, ErlNifTid};
+
 /// A `JobSpawner` is a value that can run Rust code on non-Erlang system threads.
 /// Abstracts away details of thread management for `spawn()`.
 ///
@@ -12,6 +25,41 @@ use std::thread;
 pub trait JobSpawner {
     /// Run the given closure on another thread.
     fn spawn<F: FnOnce() + Send + panic::UnwindSafe + 'static>(job: F);
+
+    /// Run the given closure on another thread, unless the spawner is at capacity.
+    ///
+    /// The default implementation always accepts the job and delegates to `spawn()`.
+    fn try_spawn<F: FnOnce() + Send + panic::UnwindSafe + 'static>(
+        job: F,
+    ) -> Result<(), SpawnError> {
+        Self::spawn(job);
+        Ok(())
+    }
+}
+
+/// Error returned by `JobSpawner::try_spawn()` when a job is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnError {
+    /// The spawner's queue is full.
+    QueueFull,
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpawnError::QueueFull => f.write_str("job queue is full"),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
+impl Encoder for SpawnError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            SpawnError::QueueFull => crate::types::atom::queue_full().encode(env),
+        }
+    }
 }
 
 /// A `JobSpawner` that uses a separate system thread for each job.
@@ -24,6 +72,525 @@ impl JobSpawner for ThreadSpawner {
     }
 }
 
+/// Configuration of the threads started by an `ErlangThreadSpawner`.
+pub trait ThreadConfig {
+    /// Name of the threads, as shown in VM tooling.
+    const NAME: &'static str = "rustler";
+    /// Stack size of the threads in bytes, or `None` for the VM's default.
+    const STACK_SIZE: Option<usize> = None;
+}
+
+/// The default `ThreadConfig`: threads named `rustler` with the VM's default stack size.
+pub struct DefaultThreadConfig;
+
+impl ThreadConfig for DefaultThreadConfig {}
+
+/// A `JobSpawner` that starts a thread with `enif_thread_create` for each job.
+///
+/// The name and stack size of the threads are taken from the `ThreadConfig` type parameter. The
+/// VM requires all threads it created to be joined, so threads that are still running when the
+/// library is unloaded are joined before unloading completes.
+///
+/// When a module is upgraded without changing its NIF library, the old and the new instance of
+/// the library share this spawner. A thread belongs to the instance that was loaded last when it
+/// was started, and is only joined when that instance is unloaded.
+pub struct ErlangThreadSpawner<C: ThreadConfig = DefaultThreadConfig>(PhantomData<C>);
+
+/// The id of the instance of the library that was loaded last, see `enter_instance()`.
+static CURRENT_INSTANCE: AtomicUsize = AtomicUsize::new(0);
+static NEXT_INSTANCE: AtomicUsize = AtomicUsize::new(1);
+
+lazy_static::lazy_static! {
+    /// The threads started by an `ErlangThreadSpawner`, with the instance they belong to.
+    static ref SPAWNED_THREADS: Mutex<Vec<(usize, JoinHandle<()>)>> = Mutex::new(Vec::new());
+}
+
+/// Make the threads started from now on belong to a new instance of the library, which is being
+/// loaded. Returns the id of the new instance and the id of the instance loaded before.
+pub(crate) fn enter_instance() -> (usize, usize) {
+    let instance = NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed);
+    (instance, CURRENT_INSTANCE.swap(instance, Ordering::AcqRel))
+}
+
+/// Undo `enter_instance()` after loading `instance` failed, joining the threads it started.
+pub(crate) fn abort_instance(instance: usize, previous: usize) {
+    let _ =
+        CURRENT_INSTANCE.compare_exchange(instance, previous, Ordering::AcqRel, Ordering::Acquire);
+    join_instance_threads(instance);
+}
+
+impl<C: ThreadConfig> JobSpawner for ErlangThreadSpawner<C> {
+    /// This delegates to `Builder::spawn()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the VM fails to create the thread, like `std::thread::spawn()`.
+    fn spawn<F: FnOnce() + Send + panic::UnwindSafe + 'static>(job: F) {
+        let mut builder = Builder::new(C::NAME);
+        if let Some(size) = C::STACK_SIZE {
+            builder = builder.stack_size(size);
+        }
+        let handle = builder.spawn(job).expect("failed to spawn thread");
+
+        let mut threads = SPAWNED_THREADS.lock().unwrap();
+        threads.retain(|(_, thread)| !thread.is_finished());
+        threads.push((CURRENT_INSTANCE.load(Ordering::Acquire), handle));
+    }
+}
+
+/// Configuration of the thread pool behind a `PoolSpawner`.
+pub trait PoolConfig: 'static {
+    /// Name of the worker threads, as shown in VM tooling.
+    const NAME: &'static str = "rustler_pool";
+    /// Number of worker threads.
+    const WORKERS: usize = 4;
+    /// Number of jobs that can wait for a free worker before new jobs are rejected.
+    const QUEUE_SIZE: usize = 64;
+}
+
+/// The default `PoolConfig`: 4 workers and room for 64 waiting jobs.
+pub struct DefaultPoolConfig;
+
+impl PoolConfig for DefaultPoolConfig {}
+
+/// A `JobSpawner` that runs jobs on a fixed pool of threads with a bounded queue.
+///
+/// There is one pool per `PoolConfig` type, started on first use. The workers are created with
+/// `enif_thread_create`, like the threads of an `ErlangThreadSpawner`. A pool belongs to the
+/// instance of the library that was loaded last when it was started: when a module is upgraded
+/// without changing its NIF library, the new instance starts its own pools. When an instance is
+/// unloaded, its pools run the jobs that are still queued, and their workers are joined.
+///
+/// When the queue is full, `try_spawn()` rejects the job. `rustler::thread::spawn()` then sends
+/// `{error, queue_full}` to the calling process instead of running the job, while `spawn()` blocks
+/// until there is room in the queue.
+pub struct PoolSpawner<C: PoolConfig = DefaultPoolConfig>(PhantomData<C>);
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Pool {
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Pool {
+    fn start<C: PoolConfig>() -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(C::QUEUE_SIZE);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..C::WORKERS.max(1))
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                Builder::new(C::NAME)
+                    .spawn(move || run_worker(&receiver))
+                    .expect("failed to spawn pool worker")
+            })
+            .collect();
+
+        Pool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        // Closing the channel makes the workers exit once the queue is drained.
+        self.sender.take();
+        self.workers.clear();
+    }
+}
+
+fn run_worker(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        match job {
+            // Jobs are `UnwindSafe`, and a panicking job must not take the worker down.
+            Ok(job) => {
+                let _ = panic::catch_unwind(panic::AssertUnwindSafe(job));
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The pools by the instance they belong to and their `PoolConfig`.
+    static ref POOLS: Mutex<HashMap<(usize, TypeId), Pool>> = Mutex::new(HashMap::new());
+}
+
+fn pool_sender<C: PoolConfig>() -> SyncSender<Job> {
+    let instance = CURRENT_INSTANCE.load(Ordering::Acquire);
+    let mut pools = POOLS.lock().unwrap_or_else(|err| err.into_inner());
+    let pool = pools
+        .entry((instance, TypeId::of::<C>()))
+        .or_insert_with(Pool::start::<C>);
+    pool.sender.clone().expect("pool is shut down")
+}
+
+impl<C: PoolConfig> JobSpawner for PoolSpawner<C> {
+    /// Queue the job, blocking while the queue is full.
+    fn spawn<F: FnOnce() + Send + panic::UnwindSafe + 'static>(job: F) {
+        pool_sender::<C>()
+            .send(Box::new(job))
+            .expect("pool workers are gone");
+    }
+
+    fn try_spawn<F: FnOnce() + Send + panic::UnwindSafe + 'static>(
+        job: F,
+    ) -> Result<(), SpawnError> {
+        match pool_sender::<C>().try_send(Box::new(job)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(SpawnError::QueueFull),
+            Err(TrySendError::Disconnected(_)) => panic!("pool workers are gone"),
+        }
+    }
+}
+
+// Unit tests run outside of the VM, where the `enif_*` functions cannot be linked.
+#[cfg(test)]
+pub(crate) fn join_instance_threads(_instance: usize) {}
+
+/// Shut down the thread pools and join the threads started by an `ErlangThreadSpawner` that
+/// belong to `instance`.
+#[cfg(not(test))]
+pub(crate) fn join_instance_threads(instance: usize) {
+    loop {
+        let pools: Vec<Pool> = {
+            let mut pools = POOLS.lock().unwrap_or_else(|err| err.into_inner());
+            let keys: Vec<_> = pools
+                .keys()
+                .filter(|(owner, _)| *owner == instance)
+                .copied()
+                .collect();
+            keys.iter().filter_map(|key| pools.remove(key)).collect()
+        };
+        let threads: Vec<_> = {
+            let mut spawned = SPAWNED_THREADS
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            let (threads, others) = mem::take(&mut *spawned)
+                .into_iter()
+                .partition(|(owner, _)| *owner == instance);
+            *spawned = others;
+            threads
+        };
+        if pools.is_empty() && threads.is_empty() {
+            break;
+        }
+        // Dropping a pool runs its queued jobs and joins its workers, and dropping a `JoinHandle`
+        // joins the thread. This happens without holding the locks, since running jobs may
+        // spawn more threads.
+        drop(pools);
+        drop(threads);
+    }
+}
+
+/// Thread factory for threads created by the VM with `enif_thread_create`, see
+/// `std::thread::Builder`.
+///
+/// Unlike threads created by `std::thread`, these are named in VM tooling and their stack size is
+/// handled by the VM.
+#[derive(Debug)]
+pub struct Builder {
+    name: String,
+    stack_size: Option<usize>,
+}
+
+impl Builder {
+    /// Create a builder for a thread called `name`.
+    pub fn new(name: &str) -> Self {
+        Builder {
+            name: name.to_owned(),
+            stack_size: None,
+        }
+    }
+
+    /// Set the suggested stack size of the new thread in bytes.
+    ///
+    /// The VM takes the size in kilowords, so it is rounded up, and the VM may clamp it.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Spawn a new thread that runs `f` and return a handle to join it.
+    ///
+    /// Fails if the name contains a nul byte or if the VM fails to create the thread.
+    pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let name = CString::new(self.name)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "nul byte in thread name"))?;
+
+        let opts = unsafe { rustler_sys::enif_thread_opts_create(name.as_ptr() as *mut _) };
+        if opts.is_null() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "enif_thread_opts_create failed",
+            ));
+        }
+        if let Some(size) = self.stack_size {
+            let word_size = mem::size_of::<usize>();
+            let kilowords = (size + 1024 * word_size - 1) / (1024 * word_size);
+            unsafe { (*opts).suggested_stack_size = kilowords.min(c_int::MAX as usize) as c_int };
+        }
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let start = Box::into_raw(Box::new(ThreadStart {
+            f,
+            finished: Arc::clone(&finished),
+        }));
+
+        let mut tid = ptr::null_mut();
+        let rc = unsafe {
+            rustler_sys::enif_thread_create(
+                name.as_ptr() as *mut _,
+                &mut tid,
+                Some(thread_main::<F, T>),
+                start as *mut c_void,
+                opts,
+            )
+        };
+        unsafe { rustler_sys::enif_thread_opts_destroy(opts) };
+
+        if rc != 0 {
+            drop(unsafe { Box::from_raw(start) });
+            return Err(io::Error::from_raw_os_error(rc));
+        }
+
+        Ok(JoinHandle {
+            tid,
+            finished,
+            result: PhantomData,
+        })
+    }
+}
+
+struct ThreadStart<F> {
+    f: F,
+    finished: Arc<AtomicBool>,
+}
+
+unsafe extern "C" fn thread_main<F, T>(arg: *mut c_void) -> *mut c_void
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let start = Box::from_raw(arg as *mut ThreadStart<F>);
+    let ThreadStart { f, finished } = *start;
+    let result: thread::Result<T> = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    finished.store(true, Ordering::Release);
+    Box::into_raw(Box::new(result)) as *mut c_void
+}
+
+/// An owned permission to join a thread created by `Builder::spawn()`.
+///
+/// Unlike `std::thread::JoinHandle`, dropping the handle does not detach the thread but waits for
+/// it to finish, since the VM does not support detached threads.
+pub struct JoinHandle<T> {
+    tid: ErlNifTid,
+    finished: Arc<AtomicBool>,
+    result: PhantomData<T>,
+}
+
+// The handle only carries the thread id; the result of type `T` is moved out of the thread by
+// `join`, like with `std::thread::JoinHandle`.
+unsafe impl<T: Send> Send for JoinHandle<T> {}
+unsafe impl<T: Send> Sync for JoinHandle<T> {}
+
+impl<T> JoinHandle<T> {
+    /// Wait for the thread to finish and return its result.
+    ///
+    /// If the thread panicked, `Err` is returned with the panic payload, like
+    /// `std::thread::JoinHandle::join()`.
+    pub fn join(self) -> thread::Result<T> {
+        let this = mem::ManuallyDrop::new(self);
+        let result = unsafe { this.join_thread() };
+        // Drop the remaining field, the thread is joined now.
+        drop(unsafe { ptr::read(&this.finished) });
+        result
+    }
+
+    /// Check whether the thread has finished running, without blocking.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// The name the thread was created with.
+    pub fn name(&self) -> Option<&str> {
+        unsafe {
+            let name = rustler_sys::enif_thread_name(self.tid);
+            if name.is_null() {
+                None
+            } else {
+                CStr::from_ptr(name).to_str().ok()
+            }
+        }
+    }
+
+    unsafe fn join_thread(&self) -> thread::Result<T> {
+        let mut result = ptr::null_mut();
+        let rc = rustler_sys::enif_thread_join(self.tid, &mut result);
+        assert_eq!(rc, 0, "enif_thread_join failed");
+        *Box::from_raw(result as *mut thread::Result<T>)
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        let _ = unsafe { self.join_thread() };
+    }
+}
+
+/// The name of the current thread if it was created by the VM, e.g. with `Builder::spawn()`.
+pub fn current_name() -> Option<String> {
+    unsafe {
+        let name = rustler_sys::enif_thread_name(rustler_sys::enif_thread_self());
+        if name.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// A key for thread-specific data, created with `enif_tsd_key_create`.
+///
+/// Each thread sees its own value for the key, like with `thread_local!`, but the key is a value
+/// that can be created at runtime, e.g. when the library is loaded, and stored in the private data
+/// or a static. This is useful for per-scheduler caches, which work on dirty schedulers as well.
+///
+/// A thread's value is initialized once and is not replaced afterwards, so references to it stay
+/// valid; use interior mutability like `RefCell` to modify it. All remaining values are dropped
+/// when the key is dropped, on the thread dropping the key.
+///
+/// # Limitations
+///
+/// The VM runs no destructor for thread-specific data, so the value of a thread that exits is
+/// only dropped together with the key, unless the thread clears it with `take()` first.
+///
+/// The VM only allows destroying a key once no thread has a value for it anymore, and a thread
+/// can only clear its own value. If other threads still have a value when the key is dropped,
+/// the key is therefore not released to the VM and leaks. Keys are best created once, e.g. when
+/// the library is loaded, and kept for the lifetime of the library.
+pub struct TsdKey<T: Send + 'static> {
+    key: rustler_sys::ErlNifTSDKey,
+    values: Mutex<Vec<*mut T>>,
+}
+
+unsafe impl<T: Send + 'static> Send for TsdKey<T> {}
+unsafe impl<T: Send + 'static> Sync for TsdKey<T> {}
+
+impl<T: Send + 'static> TsdKey<T> {
+    /// Create a new key called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a nul byte or if the VM fails to create the key.
+    pub fn new(name: &str) -> Self {
+        let name = CString::new(name).expect("key name must not contain a nul byte");
+        let mut key = 0;
+        let rc = unsafe { rustler_sys::enif_tsd_key_create(name.as_ptr() as *mut _, &mut key) };
+        assert_eq!(rc, 0, "enif_tsd_key_create failed");
+        TsdKey {
+            key,
+            values: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The value of the current thread, if it has been set.
+    pub fn get(&self) -> Option<&T> {
+        let value = unsafe { rustler_sys::enif_tsd_get(self.key) } as *const T;
+        unsafe { value.as_ref() }
+    }
+
+    /// Set the value of the current thread, unless it has been set already, in which case `value`
+    /// is returned as the error.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        if self.get().is_some() {
+            return Err(value);
+        }
+
+        let value = Box::into_raw(Box::new(value));
+        self.values
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(value);
+        unsafe { rustler_sys::enif_tsd_set(self.key, value as *mut c_void) };
+        Ok(())
+    }
+
+    /// Clear the value of the current thread and return it.
+    ///
+    /// # Safety
+    ///
+    /// References to the value returned by `get()` or `get_or_init()` on this thread must not be
+    /// used afterwards.
+    pub unsafe fn take(&self) -> Option<T> {
+        let value = rustler_sys::enif_tsd_get(self.key) as *mut T;
+        if value.is_null() {
+            return None;
+        }
+
+        rustler_sys::enif_tsd_set(self.key, ptr::null_mut());
+        let mut values = self.values.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(index) = values.iter().position(|&other| other == value) {
+            values.swap_remove(index);
+        }
+        Some(*Box::from_raw(value))
+    }
+
+    /// The value of the current thread, initialized with `init` if it has not been set yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `init` sets the value of the current thread itself.
+    pub fn get_or_init<F: FnOnce() -> T>(&self, init: F) -> &T {
+        if let Some(value) = self.get() {
+            return value;
+        }
+
+        if self.set(init()).is_err() {
+            panic!("TsdKey::get_or_init: value initialized during init");
+        }
+        self.get().unwrap()
+    }
+}
+
+impl<T: Send + 'static> Drop for TsdKey<T> {
+    fn drop(&mut self) {
+        let own_value = self.get().is_some();
+        if own_value {
+            unsafe { rustler_sys::enif_tsd_set(self.key, ptr::null_mut()) };
+        }
+
+        let values = self.values.get_mut().unwrap_or_else(|err| err.into_inner());
+        // The VM requires the data of all threads to be cleared before the key is destroyed, as
+        // the key may be reused. We can only clear our own, so the key leaks if other threads
+        // still have a value, see the limitations above.
+        if values.len() == own_value as usize {
+            unsafe { rustler_sys::enif_tsd_key_destroy(self.key) };
+        }
+
+        for value in values.drain(..) {
+            drop(unsafe { Box::from_raw(value) });
+        }
+    }
+}
+
+impl<T: Send + 'static> fmt::Debug for TsdKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TsdKey").field("key", &self.key).finish()
+    }
+}
+
 /// Implements threaded NIFs.
 ///
 /// This spawns a thread that calls the given closure `thread_fn`. When the closure returns, the
@@ -33,31 +600,42 @@ impl JobSpawner for ThreadSpawner {
 /// Note that the thread creates a new `Env` and passes it to the closure, so the closure
 /// runs under a separate environment, not under `env`.
 ///
+/// If the spawner rejects the job, e.g. because the queue of a `PoolSpawner` is full, an `{error,
+/// Reason}` tuple with the `SpawnError` is sent to the calling process right away.
+///
 pub fn spawn<'a, S, F>(env: Env<'a>, thread_fn: F)
 where
     F: for<'b> FnOnce(Env<'b>) -> Term<'b> + Send + panic::UnwindSafe + 'static,
     S: JobSpawner,
 {
     let pid = env.pid();
-    S::spawn(move || {
+    let result = S::try_spawn(move || {
         let _ = OwnedEnv::new().send_and_clear(&pid, |env| {
             match panic::catch_unwind(|| thread_fn(env)) {
                 Ok(term) => term,
-                Err(err) => {
-                    // Try to get an error message from Rust.
-                    let reason = if let Some(string) = err.downcast_ref::<String>() {
-                        string.encode(env)
-                    } else if let Some(&s) = err.downcast_ref::<&'static str>() {
-                        s.encode(env)
-                    } else {
-                        Atom::from_bytes(env, b"nif_panic")
-                            .ok()
-                            .unwrap()
-                            .to_term(env)
-                    };
-                    env.error_tuple(reason)
-                }
+                Err(err) => panic_error_tuple(env, &err),
             }
         });
     });
+
+    if let Err(err) = result {
+        let _ = env.send(&pid, env.error_tuple(err));
+    }
+}
+
+/// Build an `{error, Reason}` tuple for a caught panic, with the panic message as the reason if
+/// there is one.
+pub(crate) fn panic_error_tuple<'a>(env: Env<'a>, err: &Box<dyn Any + Send>) -> Term<'a> {
+    // Try to get an error message from Rust.
+    let reason = if let Some(string) = err.downcast_ref::<String>() {
+        string.encode(env)
+    } else if let Some(&s) = err.downcast_ref::<&'static str>() {
+        s.encode(env)
+    } else {
+        Atom::from_bytes(env, b"nif_panic")
+            .ok()
+            .unwrap()
+            .to_term(env)
+    };
+    env.error_tuple(reason)
 }
@@ -0,0 +1,218 @@
+//! Access to the clocks of the Erlang VM.
+//!
+//! The functions in this module read the same clocks as `erlang:monotonic_time/1` and
+//! `erlang:system_time/1`, so timestamps taken in a NIF can be compared with timestamps taken in
+//! Erlang or Elixir code.
+
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::Duration;
+
+use crate::types::atom;
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use rustler_sys::{ErlNifTime, ErlNifTimeUnit};
+
+/// A time unit, see [ErlNifTimeUnit](https://www.erlang.org/doc/man/erl_nif.html#ErlNifTimeUnit)
+/// in the Erlang docs.
+///
+/// Encodes to and decodes from the atoms `second`, `millisecond`, `microsecond` and
+/// `nanosecond`, like the time units of `erlang:monotonic_time/1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second = ErlNifTimeUnit::ERL_NIF_SEC as isize,
+    Millisecond = ErlNifTimeUnit::ERL_NIF_MSEC as isize,
+    Microsecond = ErlNifTimeUnit::ERL_NIF_USEC as isize,
+    Nanosecond = ErlNifTimeUnit::ERL_NIF_NSEC as isize,
+}
+
+impl TimeUnit {
+    fn as_c_arg(self) -> ErlNifTimeUnit {
+        match self {
+            TimeUnit::Second => ErlNifTimeUnit::ERL_NIF_SEC,
+            TimeUnit::Millisecond => ErlNifTimeUnit::ERL_NIF_MSEC,
+            TimeUnit::Microsecond => ErlNifTimeUnit::ERL_NIF_USEC,
+            TimeUnit::Nanosecond => ErlNifTimeUnit::ERL_NIF_NSEC,
+        }
+    }
+}
+
+impl Encoder for TimeUnit {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let unit = match self {
+            TimeUnit::Second => atom::second(),
+            TimeUnit::Millisecond => atom::millisecond(),
+            TimeUnit::Microsecond => atom::microsecond(),
+            TimeUnit::Nanosecond => atom::nanosecond(),
+        };
+        unit.encode(env)
+    }
+}
+
+impl<'a> Decoder<'a> for TimeUnit {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let unit = atom::Atom::from_term(term)?;
+        if unit == atom::second() {
+            Ok(TimeUnit::Second)
+        } else if unit == atom::millisecond() {
+            Ok(TimeUnit::Millisecond)
+        } else if unit == atom::microsecond() {
+            Ok(TimeUnit::Microsecond)
+        } else if unit == atom::nanosecond() {
+            Ok(TimeUnit::Nanosecond)
+        } else {
+            Err(Error::BadArg)
+        }
+    }
+}
+
+/// The current Erlang monotonic time in `unit`, like `erlang:monotonic_time/1`.
+///
+/// The value may be negative, and is only meaningful compared with other monotonic times of the
+/// same VM.
+pub fn monotonic_time(unit: TimeUnit) -> i64 {
+    unsafe { rustler_sys::enif_monotonic_time(unit.as_c_arg()) }
+}
+
+/// The current offset between Erlang monotonic time and Erlang system time in `unit`, like
+/// `erlang:time_offset/1`.
+pub fn time_offset(unit: TimeUnit) -> i64 {
+    unsafe { rustler_sys::enif_time_offset(unit.as_c_arg()) }
+}
+
+/// The current Erlang system time in `unit`, like `erlang:system_time/1`.
+///
+/// This is the monotonic time plus the time offset, i.e. the time since the Unix epoch as seen by
+/// the VM.
+pub fn system_time(unit: TimeUnit) -> i64 {
+    // Add in nanoseconds and convert once, as both parts would be rounded down separately in
+    // coarser units.
+    let time =
+        monotonic_time(TimeUnit::Nanosecond).saturating_add(time_offset(TimeUnit::Nanosecond));
+    convert_time_unit(time, TimeUnit::Nanosecond, unit)
+}
+
+/// Convert `time` from the unit `from` to the unit `to`, like `erlang:convert_time_unit/3`.
+///
+/// Conversions to a coarser unit round down (towards negative infinity).
+pub fn convert_time_unit(time: i64, from: TimeUnit, to: TimeUnit) -> i64 {
+    unsafe { rustler_sys::enif_convert_time_unit(time, from.as_c_arg(), to.as_c_arg()) }
+}
+
+/// The current time as a `{MegaSecs, Secs, MicroSecs}` tuple, like `erlang:now/0`.
+pub fn now_time(env: Env) -> Term {
+    unsafe { Term::new(env, rustler_sys::enif_now_time(env.as_c_arg())) }
+}
+
+/// The CPU time used by the VM as a `{MegaSecs, Secs, MicroSecs}` tuple, in the same format as
+/// `erlang:timestamp/0`.
+///
+/// If the OS does not support reading the CPU time, the VM raises a `badarg` exception in `env`
+/// right away, and the exception is returned as `Err(Error::RaiseTerm)`. The exception is pending
+/// whether or not the error is returned, so the NIF must return it rather than a value.
+pub fn cpu_time(env: Env) -> NifResult<Term> {
+    let term = unsafe { Term::new(env, rustler_sys::enif_cpu_time(env.as_c_arg())) };
+    // Without support, the result is the `badarg` exception, which has no type to inspect.
+    if unsafe { rustler_sys::enif_is_exception(env.as_c_arg(), term.as_c_arg()) } != 0 {
+        return Err(Error::RaiseTerm(Box::new(atom::badarg())));
+    }
+    Ok(term)
+}
+
+/// A point in Erlang monotonic time, with nanosecond resolution.
+///
+/// Works like `std::time::Instant`, but reads the clock of the VM. `Instant::from_monotonic_time()`
+/// and `Instant::as_monotonic_time()` convert from and to the values of
+/// `erlang:monotonic_time/1`.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn elapsed_ms(started_at: i64) -> u128 {
+///     Instant::from_monotonic_time(started_at, TimeUnit::Millisecond)
+///         .elapsed()
+///         .as_millis()
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(ErlNifTime);
+
+impl Instant {
+    /// The current monotonic time.
+    pub fn now() -> Self {
+        Instant(monotonic_time(TimeUnit::Nanosecond))
+    }
+
+    /// The instant of a value returned by `erlang:monotonic_time(Unit)`.
+    pub fn from_monotonic_time(time: i64, unit: TimeUnit) -> Self {
+        Instant(convert_time_unit(time, unit, TimeUnit::Nanosecond))
+    }
+
+    /// This instant as a monotonic time in `unit`, as returned by `erlang:monotonic_time(Unit)`.
+    pub fn as_monotonic_time(&self, unit: TimeUnit) -> i64 {
+        convert_time_unit(self.0, TimeUnit::Nanosecond, unit)
+    }
+
+    /// The time elapsed since `earlier`, or zero if `earlier` is later than this instant.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier).unwrap_or_default()
+    }
+
+    /// The time elapsed since `earlier`, or `None` if `earlier` is later than this instant.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        let nanos = self.0.checked_sub(earlier.0)?;
+        u64::try_from(nanos).ok().map(Duration::from_nanos)
+    }
+
+    /// The time elapsed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    /// `self + duration`, or `None` if the result cannot be represented.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        let nanos = i64::try_from(duration.as_nanos()).ok()?;
+        self.0.checked_add(nanos).map(Instant)
+    }
+
+    /// `self - duration`, or `None` if the result cannot be represented.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        let nanos = i64::try_from(duration.as_nanos()).ok()?;
+        self.0.checked_sub(nanos).map(Instant)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, other: Instant) -> Duration {
+        self.duration_since(other)
+    }
+}
@@ -308,4 +308,25 @@ atoms! {
 
     /// The `step` atom used by `Elixir.Range` vor Elixir >= v1.12
     step,
+
+    /// The `logger` atom, the registered name of the process rustler reports failures to.
+    logger,
+
+    /// The `log` atom, tagging the log events sent to the `logger` process.
+    log,
+
+    /// The `queue_full` atom, sent by `rustler::thread::spawn()` when the job could not be queued.
+    queue_full,
+
+    /// The `second` time unit atom.
+    second,
+
+    /// The `millisecond` time unit atom.
+    millisecond,
+
+    /// The `microsecond` time unit atom.
+    microsecond,
+
+    /// The `nanosecond` time unit atom.
+    nanosecond,
 }
@@ -21,6 +21,13 @@
 //! to that of the NIF's [`Env`]. `NewBinary` must be converted to a `Binary`
 //! or directly to a `Term` before it can be passed to Erlang.
 //!
+//! [`IoData`] builds an iolist out of existing binaries and small chunks of
+//! bytes, so large responses can reference the binaries they are made of
+//! instead of copying them into a new one.
+//!
+//! [`OwnedBinaryWriter`] and [`BinaryReader`] implement `std::io::Write` and
+//! `std::io::Read`, to plug serializers and parsers directly into binaries.
+//!
 //! # Examples
 //!
 //! Constructing an `OwnedBinary`:
@@ -83,6 +90,9 @@
 //!
 //! [`Binary`]: struct.Binary.html
 //! [`Env`]: ../../env/struct.Env.html
+//! [`IoData`]: struct.IoData.html
+//! [`OwnedBinaryWriter`]: struct.OwnedBinaryWriter.html
+//! [`BinaryReader`]: struct.BinaryReader.html
 //! [`OwnedBinary`]: struct.OwnedBinary.html
 
 use crate::{
@@ -92,7 +102,7 @@ use crate::{
 use std::{
     borrow::{Borrow, BorrowMut},
     hash::{Hash, Hasher},
-    io::Write,
+    io::{self, BufRead, Read, Write},
     mem::MaybeUninit,
     ops::{Deref, DerefMut},
 };
@@ -183,6 +193,11 @@ impl OwnedBinary {
     pub fn release(self, env: Env) -> Binary {
         Binary::from_owned(self, env)
     }
+
+    /// Consumes `self` and returns the underlying `ErlNifBinary` without releasing it.
+    pub(crate) fn into_raw(self) -> ErlNifBinary {
+        std::mem::ManuallyDrop::new(self).0
+    }
 }
 
 impl Borrow<[u8]> for OwnedBinary {
@@ -232,6 +247,118 @@ impl Drop for OwnedBinary {
 unsafe impl Send for OwnedBinary {}
 unsafe impl Sync for OwnedBinary {}
 
+/// A growable `OwnedBinary` implementing `std::io::Write`.
+///
+/// The binary grows with `enif_realloc_binary`, at least doubling its capacity each time, and is
+/// shrunk to the written size by `OwnedBinaryWriter::finish()`. This allows serializers and
+/// encoders to write straight into an Erlang binary:
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn compress<'a>(env: Env<'a>, data: Binary) -> Binary<'a> {
+///     let mut encoder = GzEncoder::new(OwnedBinaryWriter::new(), Compression::default());
+///     encoder.write_all(&data).unwrap();
+///     encoder.finish().unwrap().finish().release(env)
+/// }
+/// ```
+pub struct OwnedBinaryWriter {
+    binary: OwnedBinary,
+    len: usize,
+}
+
+impl OwnedBinaryWriter {
+    const MIN_CAPACITY: usize = 64;
+
+    /// Create an empty writer.
+    pub fn new() -> Self {
+        OwnedBinaryWriter::with_capacity(0)
+    }
+
+    /// Create an empty writer that can hold `capacity` bytes before growing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation fails.
+    pub fn with_capacity(capacity: usize) -> Self {
+        OwnedBinaryWriter {
+            binary: OwnedBinary::new(capacity).expect("allocation failed"),
+            len: 0,
+        }
+    }
+
+    /// The number of bytes written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of bytes the writer can hold before growing.
+    pub fn capacity(&self) -> usize {
+        self.binary.len()
+    }
+
+    /// The bytes written so far.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.binary[..self.len]
+    }
+
+    /// Make room for at least `additional` more bytes.
+    ///
+    /// Returns `false` if the reallocation fails, leaving the written data intact.
+    #[must_use]
+    pub fn reserve(&mut self, additional: usize) -> bool {
+        let required = match self.len.checked_add(additional) {
+            Some(required) => required,
+            None => return false,
+        };
+        if required <= self.capacity() {
+            return true;
+        }
+
+        let capacity = required
+            .max(self.capacity().saturating_mul(2))
+            .max(Self::MIN_CAPACITY);
+        self.binary.realloc(capacity)
+    }
+
+    /// Shrink the binary to the written bytes and return it.
+    pub fn finish(mut self) -> OwnedBinary {
+        if self.len != self.capacity() {
+            self.binary.realloc_or_copy(self.len);
+        }
+        self.binary
+    }
+}
+
+impl Default for OwnedBinaryWriter {
+    fn default() -> Self {
+        OwnedBinaryWriter::new()
+    }
+}
+
+impl Write for OwnedBinaryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.reserve(buf.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "could not grow the binary",
+            ));
+        }
+
+        self.binary[self.len..self.len + buf.len()].copy_from_slice(buf);
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// An immutable smart-pointer to an Erlang binary.
 ///
 /// See [module-level doc](index.html) for more information.
@@ -433,6 +560,90 @@ impl PartialEq<OwnedBinary> for Binary<'_> {
     }
 }
 
+/// A cursor over a `Binary` implementing `std::io::Read` and `std::io::BufRead`.
+///
+/// Besides reading into buffers, `BinaryReader::read_binary()` returns the next bytes as a
+/// sub-binary without copying them.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn decode<'a>(data: Binary<'a>) -> NifResult<Message<'a>> {
+///     let mut reader = BinaryReader::new(data);
+///     let mut length = [0; 4];
+///     reader.read_exact(&mut length).map_err(|_| Error::BadArg)?;
+///     let payload = reader.read_binary(u32::from_be_bytes(length) as usize)?;
+///     Ok(Message { payload })
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct BinaryReader<'a> {
+    binary: Binary<'a>,
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    /// Create a reader starting at the beginning of `binary`.
+    pub fn new(binary: Binary<'a>) -> Self {
+        BinaryReader {
+            binary,
+            position: 0,
+        }
+    }
+
+    /// The position of the reader in the binary.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Move the reader to `position`, or to the end of the binary if `position` is past it.
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position.min(self.binary.len());
+    }
+
+    /// The bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.binary.as_slice()[self.position..]
+    }
+
+    /// Read the next `length` bytes as a sub-binary, without copying them.
+    ///
+    /// Returns `Err(Error::BadArg)` if less than `length` bytes remain.
+    pub fn read_binary(&mut self, length: usize) -> NifResult<Binary<'a>> {
+        let binary = self.binary.make_subbinary(self.position, length)?;
+        self.position += length;
+        Ok(binary)
+    }
+
+    /// The binary being read.
+    pub fn into_inner(self) -> Binary<'a> {
+        self.binary
+    }
+}
+
+impl<'a> From<Binary<'a>> for BinaryReader<'a> {
+    fn from(binary: Binary<'a>) -> Self {
+        BinaryReader::new(binary)
+    }
+}
+
+impl Read for BinaryReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.remaining().read(buf)?;
+        self.position += count;
+        Ok(count)
+    }
+}
+
+impl BufRead for BinaryReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.set_position(self.position.saturating_add(amt));
+    }
+}
+
 /// ## Binary terms
 impl<'a> Term<'a> {
     pub fn into_binary(self) -> NifResult<Binary<'a>> {
@@ -491,3 +702,118 @@ impl<'a> DerefMut for NewBinary<'a> {
         self.as_mut_slice()
     }
 }
+
+/// A builder for iodata made of existing binaries and copied bytes.
+///
+/// Binaries pushed into an `IoData`, including sub-binaries and resource binaries, are
+/// referenced by the resulting iolist instead of being copied. Bytes pushed with
+/// `IoData::push_bytes()` are meant for small chunks like headers and separators: they are
+/// buffered, with consecutive ones combined, and the buffer is copied into a new binary when the
+/// iolist is built, so they are copied twice. Write larger data into an `OwnedBinary` and push it
+/// with `IoData::push_owned()` instead.
+/// The resulting term is always a proper list of binaries, which is accepted wherever Erlang
+/// expects iodata.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn frame<'a>(env: Env<'a>, payload: Binary<'a>) -> IoData<'a> {
+///     let mut data = IoData::new(env);
+///     data.push_bytes(&(payload.len() as u32).to_be_bytes())
+///         .push_binary(payload);
+///     data
+/// }
+/// ```
+pub struct IoData<'a> {
+    env: Env<'a>,
+    parts: Vec<IoDataPart<'a>>,
+    len: usize,
+}
+
+enum IoDataPart<'a> {
+    Binary(Binary<'a>),
+    Bytes(Vec<u8>),
+}
+
+impl<'a> IoData<'a> {
+    /// Create an empty `IoData`.
+    pub fn new(env: Env<'a>) -> Self {
+        IoData {
+            env,
+            parts: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// The total number of bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append `binary` without copying its data.
+    pub fn push_binary(&mut self, binary: Binary<'a>) -> &mut Self {
+        self.len += binary.len();
+        self.parts.push(IoDataPart::Binary(binary));
+        self
+    }
+
+    /// Append the bytes `offset..offset + length` of `binary` as a sub-binary, without copying
+    /// them.
+    ///
+    /// Returns `Err(Error::BadArg)` if the range is out of bounds, see
+    /// `Binary::make_subbinary()`.
+    pub fn push_subbinary(
+        &mut self,
+        binary: Binary<'a>,
+        offset: usize,
+        length: usize,
+    ) -> NifResult<&mut Self> {
+        let subbinary = binary.make_subbinary(offset, length)?;
+        Ok(self.push_binary(subbinary))
+    }
+
+    /// Append `binary`, transferring its data to the environment without copying it.
+    pub fn push_owned(&mut self, binary: OwnedBinary) -> &mut Self {
+        let binary = binary.release(self.env);
+        self.push_binary(binary)
+    }
+
+    /// Append a copy of `bytes`.
+    ///
+    /// The bytes are copied into a buffer now, and into a binary when the iolist is built.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.len += bytes.len();
+        match self.parts.last_mut() {
+            Some(IoDataPart::Bytes(buffer)) => buffer.extend_from_slice(bytes),
+            _ => self.parts.push(IoDataPart::Bytes(bytes.to_vec())),
+        }
+        self
+    }
+
+    /// Build the iolist.
+    pub fn into_term(self) -> Term<'a> {
+        self.encode(self.env)
+    }
+}
+
+impl<'a> Encoder for IoData<'a> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        let terms: Vec<Term<'b>> = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                IoDataPart::Binary(binary) => binary.encode(env),
+                IoDataPart::Bytes(bytes) => {
+                    let mut binary = NewBinary::new(env, bytes.len());
+                    binary.as_mut_slice().copy_from_slice(bytes);
+                    binary.into()
+                }
+            })
+            .collect();
+        terms.encode(env)
+    }
+}
@@ -0,0 +1,265 @@
+//! Bitstrings, i.e. binaries whose size is not a whole number of bytes.
+
+use crate::dynamic::TermType;
+use crate::{Binary, Decoder, Encoder, Env, Error, NewBinary, NifResult, OwnedBinary, Term};
+
+// From https://www.erlang.org/doc/apps/erts/erl_ext_dist.html
+const EXTERNAL_TERM_FORMAT_VERSION: u8 = 131;
+const BIT_BINARY_EXT: u8 = 77;
+const BIT_BINARY_HEADER_SIZE: usize = 7;
+
+/// An immutable reference to an Erlang bitstring, e.g. `<<1::3, 5::5, 7::4>>`.
+///
+/// The bits are stored in `as_bytes()`, most significant bit first. When `bit_size()` is not a
+/// multiple of 8, only the `trailing_bits()` most significant bits of the last byte are part of
+/// the bitstring, and the rest of that byte must be ignored.
+///
+/// Every binary is a bitstring, and is decoded without copying. The NIF API cannot inspect other
+/// bitstrings directly, so those are copied once through the external term format.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn first_bits(bits: Bitstring, count: usize) -> NifResult<Bitstring> {
+///     bits.sub_bitstring(0, count)
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Bitstring<'a> {
+    data: Binary<'a>,
+    bit_size: usize,
+    term: Term<'a>,
+}
+
+impl<'a> Bitstring<'a> {
+    /// Create a bitstring from the first `bit_size` bits of `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` has less than `bit_size` bits.
+    pub fn new(env: Env<'a>, bytes: &[u8], bit_size: usize) -> Self {
+        assert!(
+            bit_size <= bytes.len() * 8,
+            "bit size is larger than the given bytes"
+        );
+        let bytes = &bytes[..byte_size(bit_size)];
+
+        if bit_size % 8 == 0 {
+            let mut binary = NewBinary::new(env, bytes.len());
+            binary.as_mut_slice().copy_from_slice(bytes);
+            let data: Binary = binary.into();
+            return Bitstring {
+                data,
+                bit_size,
+                term: data.to_term(env),
+            };
+        }
+
+        let mut encoded =
+            OwnedBinary::new(BIT_BINARY_HEADER_SIZE + bytes.len()).expect("allocation failed");
+        encode_bit_binary(encoded.as_mut_slice(), bytes, bit_size);
+        let encoded = encoded.release(env);
+
+        let (term, _) = env
+            .binary_to_term(encoded.as_slice())
+            .expect("invalid bitstring encoding");
+        Bitstring {
+            data: encoded
+                .make_subbinary(BIT_BINARY_HEADER_SIZE, bytes.len())
+                .unwrap(),
+            bit_size,
+            term,
+        }
+    }
+
+    /// Decode `term` as a bitstring.
+    ///
+    /// Returns `Err(Error::BadArg)` if `term` is not a bitstring.
+    pub fn from_term(term: Term<'a>) -> NifResult<Self> {
+        if term.is_binary() {
+            let data = Binary::from_term(term)?;
+            return Ok(Bitstring {
+                data,
+                bit_size: data.len() * 8,
+                term,
+            });
+        }
+
+        // Reject other terms before serializing them. `enif_term_type` reports bitstrings as
+        // binaries and unsupported terms as `Unknown`. Without it, `Unknown` means that none of
+        // the `enif_is_*` checks matched, which only leaves bitstrings.
+        let bitstring_type =
+            if cfg!(feature = "nif_version_2_15") && !cfg!(target_family = "windows") {
+                TermType::Binary
+            } else {
+                TermType::Unknown
+            };
+        if term.get_type() != bitstring_type {
+            return Err(Error::BadArg);
+        }
+
+        let encoded = term.to_binary();
+        let (size, bit_size) = decode_bit_binary_header(&encoded)?;
+        let encoded = encoded.release(term.get_env());
+        Ok(Bitstring {
+            data: encoded.make_subbinary(BIT_BINARY_HEADER_SIZE, size)?,
+            bit_size,
+            term,
+        })
+    }
+
+    /// The bytes holding the bits of the bitstring.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data.as_slice()
+    }
+
+    /// The size of the bitstring in bits.
+    pub fn bit_size(&self) -> usize {
+        self.bit_size
+    }
+
+    /// The number of bits in the last, partial byte, or 0 if the size is a whole number of bytes.
+    pub fn trailing_bits(&self) -> u8 {
+        (self.bit_size % 8) as u8
+    }
+
+    /// Whether the bitstring is a binary, i.e. its size is a whole number of bytes.
+    pub fn is_binary(&self) -> bool {
+        self.trailing_bits() == 0
+    }
+
+    /// The bitstring as a `Binary`, or `None` if it is not a binary.
+    pub fn as_binary(&self) -> Option<Binary<'a>> {
+        if self.is_binary() {
+            Some(self.data)
+        } else {
+            None
+        }
+    }
+
+    /// The bits `bit_offset..bit_offset + bit_length` of the bitstring.
+    ///
+    /// Byte-aligned parts are sub-binaries referencing the original data, other parts are copied.
+    /// Returns `Err(Error::BadArg)` if the range is out of bounds.
+    pub fn sub_bitstring(&self, bit_offset: usize, bit_length: usize) -> NifResult<Bitstring<'a>> {
+        match bit_offset.checked_add(bit_length) {
+            Some(end) if end <= self.bit_size => {}
+            _ => return Err(Error::BadArg),
+        }
+
+        if bit_offset % 8 == 0 && bit_length % 8 == 0 {
+            let data = self.data.make_subbinary(bit_offset / 8, bit_length / 8)?;
+            return Ok(Bitstring {
+                data,
+                bit_size: bit_length,
+                term: data.to_term(self.term.get_env()),
+            });
+        }
+
+        let bytes = copy_bits(self.as_bytes(), bit_offset, bit_length);
+        Ok(Bitstring::new(self.term.get_env(), &bytes, bit_length))
+    }
+
+    /// Return a term representing the bitstring.
+    pub fn to_term<'b>(&self, env: Env<'b>) -> Term<'b> {
+        self.term.in_env(env)
+    }
+}
+
+impl<'a> Decoder<'a> for Bitstring<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Bitstring::from_term(term)
+    }
+}
+
+impl Encoder for Bitstring<'_> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        self.to_term(env)
+    }
+}
+
+fn byte_size(bit_size: usize) -> usize {
+    (bit_size + 7) / 8
+}
+
+/// Write `BIT_BINARY_EXT` for the first `bit_size` bits of `bytes` into `out`.
+fn encode_bit_binary(out: &mut [u8], bytes: &[u8], bit_size: usize) {
+    let trailing_bits = (bit_size % 8) as u8;
+    out[0] = EXTERNAL_TERM_FORMAT_VERSION;
+    out[1] = BIT_BINARY_EXT;
+    out[2..6].copy_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out[6] = trailing_bits;
+    out[BIT_BINARY_HEADER_SIZE..].copy_from_slice(bytes);
+    if let Some(last) = out.last_mut() {
+        *last &= !(0xff >> trailing_bits);
+    }
+}
+
+/// Read the size in bytes and the size in bits of `BIT_BINARY_EXT`.
+fn decode_bit_binary_header(input: &[u8]) -> NifResult<(usize, usize)> {
+    if input.len() < BIT_BINARY_HEADER_SIZE
+        || input[0] != EXTERNAL_TERM_FORMAT_VERSION
+        || input[1] != BIT_BINARY_EXT
+    {
+        return Err(Error::BadArg);
+    }
+
+    let size = u32::from_be_bytes([input[2], input[3], input[4], input[5]]) as usize;
+    let bits = input[6] as usize;
+    if size == 0 || !(1..=8).contains(&bits) || input.len() != BIT_BINARY_HEADER_SIZE + size {
+        return Err(Error::BadArg);
+    }
+
+    Ok((size, (size - 1) * 8 + bits))
+}
+
+/// Copy `bit_length` bits starting at `bit_offset` into new bytes, most significant bit first.
+fn copy_bits(bytes: &[u8], bit_offset: usize, bit_length: usize) -> Vec<u8> {
+    let mut out = vec![0; byte_size(bit_length)];
+    let shift = bit_offset % 8;
+    let start = bit_offset / 8;
+
+    for (i, byte) in out.iter_mut().enumerate() {
+        let high = bytes[start + i] << shift;
+        let low = match bytes.get(start + i + 1) {
+            Some(next) if shift > 0 => next >> (8 - shift),
+            _ => 0,
+        };
+        *byte = high | low;
+    }
+
+    let trailing_bits = bit_length % 8;
+    if let (Some(last), true) = (out.last_mut(), trailing_bits > 0) {
+        *last &= !(0xff >> trailing_bits);
+    }
+    out
+}
+
+#[test]
+fn test_encode_bit_binary() {
+    // :erlang.term_to_binary(<<1::3, 5::5, 7::4>>)
+    let mut out = [0; 9];
+    encode_bit_binary(&mut out, &[0x25, 0x7f], 12);
+    assert_eq!(out, [131, 77, 0, 0, 0, 2, 4, 0x25, 0x70]);
+}
+
+#[test]
+fn test_decode_bit_binary_header() {
+    assert_eq!(
+        decode_bit_binary_header(&[131, 77, 0, 0, 0, 2, 4, 0x25, 0x70]).ok(),
+        Some((2, 12))
+    );
+    assert!(decode_bit_binary_header(&[131, 109, 0, 0, 0, 1, 1]).is_err());
+    assert!(decode_bit_binary_header(&[131, 77, 0, 0, 0, 1, 0, 1]).is_err());
+    assert!(decode_bit_binary_header(&[131, 77, 0, 0, 0, 2, 4, 0x25]).is_err());
+}
+
+#[test]
+fn test_copy_bits() {
+    // <<1::3, 5::5, 7::4>>
+    let bytes = [0x25, 0x70];
+    assert_eq!(copy_bits(&bytes, 0, 3), [0x20]);
+    assert_eq!(copy_bits(&bytes, 3, 5), [0x28]);
+    assert_eq!(copy_bits(&bytes, 8, 4), [0x70]);
+    assert_eq!(copy_bits(&bytes, 3, 9), [0x2b, 0x80]);
+    assert_eq!(copy_bits(&bytes, 0, 0), [] as [u8; 0]);
+}
@@ -0,0 +1,123 @@
+//! Zero-copy access to lists of binaries.
+
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use rustler_sys::ErlNifIOVec;
+
+use crate::io_queue::Chunks;
+use crate::{Decoder, Error, NifResult, Term};
+
+/// A binary or a list of binaries, inspected without copying the data of large binaries, see
+/// [enif_inspect_iovec](https://www.erlang.org/doc/man/erl_nif.html#enif_inspect_iovec).
+///
+/// The list must be flat, like the ones returned by `erlang:iolist_to_iovec/1`. Nested iolists
+/// can be decoded as a `Binary` instead, which flattens them into a copy.
+///
+/// Very long lists can be inspected in parts with `IoVec::from_term_with_max()`, continuing with
+/// `IoVec::tail()`.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn checksum(data: IoVec) -> u32 {
+///     let mut hasher = crc32fast::Hasher::new();
+///     for chunk in data.chunks() {
+///         hasher.update(chunk);
+///     }
+///     hasher.finalize()
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct IoVec<'a> {
+    iovec: *mut ErlNifIOVec,
+    tail: Term<'a>,
+}
+
+impl<'a> IoVec<'a> {
+    /// Inspect all the binaries of `term`.
+    ///
+    /// Returns `Err(Error::BadArg)` if `term` is neither a binary nor a list of binaries.
+    pub fn from_term(term: Term<'a>) -> NifResult<Self> {
+        IoVec::from_term_with_max(term, usize::MAX)
+    }
+
+    /// Inspect at most `max_elements` binaries of `term`.
+    ///
+    /// The rest of the list is available with `IoVec::tail()`. Returns `Err(Error::BadArg)` if
+    /// `term` is neither a binary nor a list of binaries.
+    pub fn from_term_with_max(term: Term<'a>, max_elements: usize) -> NifResult<Self> {
+        let env = term.get_env();
+        let list = if term.is_binary() {
+            Term::list_new_empty(env).list_prepend(term)
+        } else {
+            term
+        };
+
+        // The iovec is allocated in `env`, and freed together with its terms.
+        let mut iovec: *mut ErlNifIOVec = ptr::null_mut();
+        let mut tail = MaybeUninit::uninit();
+        let success = unsafe {
+            rustler_sys::enif_inspect_iovec(
+                env.as_c_arg(),
+                max_elements,
+                list.as_c_arg(),
+                tail.as_mut_ptr(),
+                &mut iovec,
+            )
+        };
+        if success == 0 || iovec.is_null() {
+            return Err(Error::BadArg);
+        }
+
+        Ok(IoVec {
+            iovec,
+            tail: unsafe { Term::new(env, tail.assume_init()) },
+        })
+    }
+
+    pub(crate) fn as_c_arg(&self) -> *mut ErlNifIOVec {
+        self.iovec
+    }
+
+    /// The total number of bytes.
+    pub fn len(&self) -> usize {
+        unsafe { (*self.iovec).size }
+    }
+
+    /// Whether there are no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The slices of data, in order.
+    pub fn chunks(&self) -> Chunks<'a> {
+        unsafe {
+            let iovec = &*self.iovec;
+            Chunks::from_raw(iovec.iov, iovec.iovcnt)
+        }
+    }
+
+    /// The rest of the list that was not inspected, or `None` if the whole list was inspected.
+    pub fn tail(&self) -> Option<Term<'a>> {
+        if self.tail.is_empty_list() {
+            None
+        } else {
+            Some(self.tail)
+        }
+    }
+
+    /// Copy all the data into a `Vec`.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len());
+        for chunk in self.chunks() {
+            data.extend_from_slice(chunk);
+        }
+        data
+    }
+}
+
+impl<'a> Decoder<'a> for IoVec<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        IoVec::from_term(term)
+    }
+}
@@ -0,0 +1,127 @@
+use crate::wrapper::ErlNifPort;
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use std::mem::MaybeUninit;
+use std::ptr;
+
+/// A port on the local node, see
+/// [ErlNifPort](https://www.erlang.org/doc/man/erl_nif.html#ErlNifPort).
+#[derive(Copy, Clone)]
+pub struct LocalPort {
+    c: ErlNifPort,
+}
+
+/// Returned when `Env::port_command` could not send the data.
+#[derive(Clone, Copy, Debug)]
+pub struct PortCommandError;
+
+impl LocalPort {
+    pub fn as_c_arg(&self) -> &ErlNifPort {
+        &self.c
+    }
+
+    pub fn from_c_arg(erl_nif_port: ErlNifPort) -> Self {
+        LocalPort { c: erl_nif_port }
+    }
+
+    /// Check whether the given port is alive
+    pub fn is_alive(self, env: Env) -> bool {
+        env.is_port_alive(self)
+    }
+}
+
+impl<'a> Decoder<'a> for LocalPort {
+    fn decode(term: Term<'a>) -> NifResult<LocalPort> {
+        let mut port = MaybeUninit::uninit();
+        let res = unsafe {
+            rustler_sys::enif_get_local_port(
+                term.get_env().as_c_arg(),
+                term.as_c_arg(),
+                port.as_mut_ptr(),
+            )
+        };
+
+        if res == 0 {
+            Err(Error::BadArg)
+        } else {
+            Ok(LocalPort {
+                c: unsafe { port.assume_init() },
+            })
+        }
+    }
+}
+
+impl Encoder for LocalPort {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        unsafe { Term::new(env, rustler_sys::enif_make_port(env.as_c_arg(), self.c)) }
+    }
+}
+
+impl<'a> Env<'a> {
+    /// Attempts to find the port registered by `name_or_port`
+    ///
+    /// Safe wrapper around [`enif_whereis_port`](https://www.erlang.org/doc/man/erl_nif.html#enif_whereis_port).
+    ///
+    /// # Returns
+    /// - `Some(port)` if `name_or_port` is already a local port.
+    /// - `Some(port)` if `name_or_port` is an atom and an open port is currently registered under the given name.
+    /// - `None` otherwise.
+    pub fn whereis_port(self, name_or_port: impl Encoder) -> Option<LocalPort> {
+        let name_or_port = name_or_port.encode(self);
+        if name_or_port.is_port() {
+            return name_or_port.decode().ok();
+        }
+
+        let mut port = MaybeUninit::uninit();
+
+        if unsafe {
+            rustler_sys::enif_whereis_port(
+                self.as_c_arg(),
+                name_or_port.as_c_arg(),
+                port.as_mut_ptr(),
+            )
+        } == 0
+        {
+            None
+        } else {
+            // Safety: Initialized by successful enif_whereis_port call
+            Some(LocalPort {
+                c: unsafe { port.assume_init() },
+            })
+        }
+    }
+
+    /// Checks whether the given port is alive
+    pub fn is_port_alive(self, port: LocalPort) -> bool {
+        let res = unsafe { rustler_sys::enif_is_port_alive(self.as_c_arg(), port.as_c_arg()) };
+        res != 0
+    }
+
+    /// Send `data` to a port, like `erlang:port_command/2`.
+    ///
+    /// `data` has to encode to iodata. It is handed to the port driver's output callback, so the
+    /// port replies to its owner, not necessarily to the calling process. See
+    /// [enif\_port\_command](https://www.erlang.org/doc/man/erl_nif.html#enif_port_command).
+    ///
+    /// Fails if `port` is closed, if the calling process is not alive or if `data` is not iodata.
+    pub fn port_command(
+        self,
+        port: &LocalPort,
+        data: impl Encoder,
+    ) -> Result<(), PortCommandError> {
+        let data = data.encode(self);
+        let res = unsafe {
+            rustler_sys::enif_port_command(
+                self.as_c_arg(),
+                port.as_c_arg(),
+                ptr::null_mut(),
+                data.as_c_arg(),
+            )
+        };
+
+        if res == 0 {
+            Err(PortCommandError)
+        } else {
+            Ok(())
+        }
+    }
+}
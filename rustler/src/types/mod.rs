@@ -6,7 +6,15 @@ pub mod i128;
 pub use crate::types::atom::Atom;
 
 pub mod binary;
-pub use crate::types::binary::{Binary, NewBinary, OwnedBinary};
+pub use crate::types::binary::{
+    Binary, BinaryReader, IoData, NewBinary, OwnedBinary, OwnedBinaryWriter,
+};
+
+pub mod bitstring;
+pub use crate::types::bitstring::Bitstring;
+
+pub mod iovec;
+pub use crate::types::iovec::IoVec;
 
 #[cfg(feature = "big_integer")]
 pub mod big_int;
@@ -31,6 +39,10 @@ pub mod tuple;
 pub mod local_pid;
 pub use self::local_pid::LocalPid;
 
+#[doc(hidden)]
+pub mod local_port;
+pub use self::local_port::{LocalPort, PortCommandError};
+
 #[deprecated(since = "0.22.0", note = "Please use local_pid instead")]
 pub mod pid {
     #[deprecated(since = "0.22.0", note = "Please use LocalPid instead")]
@@ -39,6 +51,9 @@ pub mod pid {
 #[deprecated(since = "0.22.0", note = "Please use LocalPid instead")]
 pub use self::LocalPid as Pid;
 
+pub mod reference;
+pub use self::reference::{OwnedReference, Reference};
+
 pub mod truthy;
 
 pub mod elixir_struct;
@@ -0,0 +1,108 @@
+use std::ops::Deref;
+
+use crate::env::SavedTerm;
+use crate::{Decoder, Encoder, Env, Error, NifResult, OwnedEnv, Term};
+
+/// Wrapper for BEAM reference terms.
+///
+/// References are unique within a running system, which makes them useful to correlate requests
+/// and replies, like `GenServer.call/3` does. New references are created with `Env::make_ref`.
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Reference<'a>(Term<'a>);
+
+impl<'a> Reference<'a> {
+    /// Returns a representation of self in the given Env.
+    ///
+    /// If the term is already is in the provided env, it will be directly returned. Otherwise
+    /// the term will be copied over.
+    pub fn in_env<'b>(&self, env: Env<'b>) -> Reference<'b> {
+        Reference(self.0.in_env(env))
+    }
+}
+
+impl<'a> Deref for Reference<'a> {
+    type Target = Term<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> From<Reference<'a>> for Term<'a> {
+    fn from(term: Reference<'a>) -> Self {
+        term.0
+    }
+}
+
+impl<'a> TryFrom<Term<'a>> for Reference<'a> {
+    type Error = Error;
+
+    fn try_from(term: Term<'a>) -> Result<Self, Self::Error> {
+        if term.is_ref() {
+            Ok(Reference(term))
+        } else {
+            Err(Error::BadArg)
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for Reference<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        term.try_into()
+    }
+}
+
+impl<'a> Encoder for Reference<'a> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        self.0.encode(env)
+    }
+}
+
+/// A reference that lives in its own `OwnedEnv`.
+///
+/// Unlike `Reference`, this is not bound to the lifetime of an `Env`, so it can be moved to another
+/// thread and loaded again later, e.g. to send a reply tagged with the reference of the original
+/// request.
+///
+/// Like `OwnedEnv`, it is `Send` but not `Sync`. To keep one in a resource, which has to be
+/// `Sync`, wrap it in a `Mutex`:
+///
+/// ```ignore
+/// struct Request {
+///     reference: Mutex<Option<OwnedReference>>,
+/// }
+///
+/// impl Resource for Request {}
+/// ```
+pub struct OwnedReference {
+    env: OwnedEnv,
+    reference: SavedTerm,
+}
+
+impl OwnedReference {
+    /// Copy `reference` into a new `OwnedEnv`.
+    pub fn new(reference: Reference) -> Self {
+        let env = OwnedEnv::new();
+        let reference = env.save(reference);
+        OwnedReference { env, reference }
+    }
+
+    /// Copy the reference into `env`.
+    pub fn load<'a>(&self, env: Env<'a>) -> Reference<'a> {
+        self.env
+            .run(|owned_env| Reference(self.reference.load(owned_env).in_env(env)))
+    }
+}
+
+impl Encoder for OwnedReference {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.load(env).into()
+    }
+}
+
+impl<'a> Env<'a> {
+    /// Create a new reference in this environment, like `erlang:make_ref/0`.
+    pub fn make_ref(self) -> Reference<'a> {
+        unsafe { Reference(Term::new(self, rustler_sys::enif_make_ref(self.as_c_arg()))) }
+    }
+}
@@ -22,8 +22,9 @@ pub mod tuple;
 pub use rustler_sys::{
     enif_clear_env, enif_free_env, enif_get_local_pid, enif_make_pid, enif_map_iterator_create,
     enif_map_iterator_destroy, enif_map_iterator_get_pair, enif_map_iterator_next, enif_self,
-    ErlNifMapIterator, ErlNifMapIteratorEntry, ErlNifPid, ERL_NIF_THR_DIRTY_CPU_SCHEDULER,
-    ERL_NIF_THR_DIRTY_IO_SCHEDULER, ERL_NIF_THR_NORMAL_SCHEDULER, ERL_NIF_THR_UNDEFINED,
+    ErlNifMapIterator, ErlNifMapIteratorEntry, ErlNifMonitor, ErlNifPid, ErlNifPort,
+    ERL_NIF_THR_DIRTY_CPU_SCHEDULER, ERL_NIF_THR_DIRTY_IO_SCHEDULER, ERL_NIF_THR_NORMAL_SCHEDULER,
+    ERL_NIF_THR_UNDEFINED,
 };
 
 pub use rustler_sys::{c_char, c_double, c_int, c_uchar, c_uint, c_void};
@@ -43,6 +44,7 @@ pub type MUTABLE_NIF_RESOURCE_HANDLE = *mut c_void;
 pub type NifResourceDtor =
     unsafe extern "C" fn(r_env: NIF_ENV, obj: MUTABLE_NIF_RESOURCE_HANDLE) -> ();
 pub type NifResourceFlags = rustler_sys::ErlNifResourceFlags;
+pub type NifResourceTypeInit = rustler_sys::ErlNifResourceTypeInit;
 
 pub enum NIF_ERROR {
     BAD_ARG,
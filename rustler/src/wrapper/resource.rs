@@ -1,5 +1,6 @@
 use crate::wrapper::{
-    NifResourceDtor, NifResourceFlags, NIF_ENV, NIF_RESOURCE_HANDLE, NIF_RESOURCE_TYPE, NIF_TERM,
+    ErlNifMonitor, ErlNifPid, NifResourceDtor, NifResourceFlags, NifResourceTypeInit, NIF_ENV,
+    NIF_RESOURCE_HANDLE, NIF_RESOURCE_TYPE, NIF_TERM,
 };
 
 use rustler_sys::c_char;
@@ -35,6 +36,51 @@ pub unsafe fn open_resource_type(
     }
 }
 
+pub unsafe fn open_resource_type_x(
+    env: NIF_ENV,
+    name: &[u8],
+    init: &NifResourceTypeInit,
+    flags: NifResourceFlags,
+) -> Option<NIF_RESOURCE_TYPE> {
+    // Panic if name is not null-terminated.
+    assert_eq!(name.last().cloned(), Some(0u8));
+
+    let name_p = name.as_ptr() as *const c_char;
+    let res = {
+        let mut tried = MaybeUninit::uninit();
+        rustler_sys::enif_open_resource_type_x(env, name_p, init, flags, tried.as_mut_ptr())
+    };
+
+    if res.is_null() {
+        None
+    } else {
+        Some(res)
+    }
+}
+
+#[cfg(feature = "nif_version_2_16")]
+pub unsafe fn init_resource_type(
+    env: NIF_ENV,
+    name: &[u8],
+    init: &NifResourceTypeInit,
+    flags: NifResourceFlags,
+) -> Option<NIF_RESOURCE_TYPE> {
+    // Panic if name is not null-terminated.
+    assert_eq!(name.last().cloned(), Some(0u8));
+
+    let name_p = name.as_ptr() as *const c_char;
+    let res = {
+        let mut tried = MaybeUninit::uninit();
+        rustler_sys::enif_init_resource_type(env, name_p, init, flags, tried.as_mut_ptr())
+    };
+
+    if res.is_null() {
+        None
+    } else {
+        Some(res)
+    }
+}
+
 // Functionally incomplete
 pub unsafe fn get_resource(
     env: NIF_ENV,
@@ -50,3 +96,26 @@ pub unsafe fn get_resource(
         Some(ret_obj.assume_init())
     }
 }
+
+pub unsafe fn monitor_process(
+    env: NIF_ENV,
+    obj: NIF_RESOURCE_HANDLE,
+    pid: &ErlNifPid,
+) -> Option<ErlNifMonitor> {
+    let mut mon = MaybeUninit::uninit();
+    let res = rustler_sys::enif_monitor_process(env, obj, pid, mon.as_mut_ptr());
+
+    if res == 0 {
+        Some(mon.assume_init())
+    } else {
+        None
+    }
+}
+
+pub unsafe fn demonitor_process(
+    env: NIF_ENV,
+    obj: NIF_RESOURCE_HANDLE,
+    mon: &ErlNifMonitor,
+) -> bool {
+    rustler_sys::enif_demonitor_process(env, obj, mon) == 0
+}
@@ -1,15 +1,18 @@
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::TokenStream;
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
-use syn::{Expr, Ident, Result, Token};
+use syn::{Expr, Result, Token};
 
 #[derive(Debug)]
 pub struct InitMacroInput {
     name: syn::Lit,
     funcs: syn::ExprArray,
-    load: TokenStream,
+    resources: Vec<syn::Path>,
+    load: Option<Expr>,
+    upgrade: Option<Expr>,
+    unload: Option<Expr>,
 }
 
 impl Parse for InitMacroInput {
@@ -18,9 +21,19 @@ impl Parse for InitMacroInput {
         let _comma = <syn::Token![,]>::parse(input)?;
         let funcs = syn::ExprArray::parse(input)?;
         let options = parse_expr_assigns(input);
-        let load = extract_option(options, "load");
+        let resources = extract_resources(&options)?;
+        let load = find_option(&options, "load").cloned();
+        let upgrade = find_option(&options, "upgrade").cloned();
+        let unload = find_option(&options, "unload").cloned();
 
-        Ok(InitMacroInput { name, funcs, load })
+        Ok(InitMacroInput {
+            name,
+            funcs,
+            resources,
+            load,
+            upgrade,
+            unload,
+        })
     }
 }
 
@@ -36,28 +49,101 @@ fn parse_expr_assigns(input: ParseStream) -> Vec<syn::ExprAssign> {
     vec
 }
 
-fn extract_option(args: Vec<syn::ExprAssign>, name: &str) -> TokenStream {
-    for syn::ExprAssign { left, right, .. } in args.into_iter() {
-        if let syn::Expr::Path(syn::ExprPath { path, .. }) = &*left {
+fn find_option<'a>(args: &'a [syn::ExprAssign], name: &str) -> Option<&'a Expr> {
+    for syn::ExprAssign { left, right, .. } in args {
+        if let syn::Expr::Path(syn::ExprPath { path, .. }) = &**left {
             if let Some(ident) = path.get_ident() {
                 if *ident == name {
-                    let value = *right;
-                    return quote!(Some(#value));
+                    return Some(right);
                 }
             }
         }
     }
 
-    let none = Ident::new("None", Span::call_site());
-    quote!(#none)
+    None
+}
+
+fn extract_resources(args: &[syn::ExprAssign]) -> Result<Vec<syn::Path>> {
+    match find_option(args, "resources") {
+        Some(Expr::Array(array)) => array
+            .elems
+            .iter()
+            .map(|elem| match elem {
+                Expr::Path(syn::ExprPath { path, .. }) => Ok(path.clone()),
+                _ => Err(syn::Error::new_spanned(
+                    elem,
+                    "Expected a resource type in `resources`",
+                )),
+            })
+            .collect(),
+        Some(value) => Err(syn::Error::new_spanned(
+            value,
+            "Expected a list of resource types (i.e. `resources = [MyResource]`)",
+        )),
+        None => Ok(Vec::new()),
+    }
 }
 
 impl From<InitMacroInput> for proc_macro2::TokenStream {
     fn from(input: InitMacroInput) -> Self {
         let name = input.name;
         let num_of_funcs = input.funcs.elems.len();
+        let func_paths: Vec<Expr> = input.funcs.elems.iter().cloned().collect();
         let funcs = nif_funcs(input.funcs.elems);
-        let load = input.load;
+        let load = match input.load {
+            Some(load) => load_callback(&load),
+            None => quote!(None),
+        };
+        let resources = input.resources;
+        let resources = quote! {
+            &[
+                {
+                    fn register_yield_state(
+                        env: rustler::Env
+                    ) -> Result<(), rustler::resource::ResourceInitError> {
+                        if false #(|| <#func_paths as rustler::Nif>::USES_YIELD_STATE)* {
+                            rustler::codegen_runtime::register_resource::<rustler::schedule::YieldState>(env)
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    register_yield_state
+                },
+                #(rustler::codegen_runtime::register_resource::<#resources>),*
+            ]
+        };
+
+        let upgrade = match input.upgrade {
+            Some(upgrade) => {
+                let upgrade = upgrade_callback(&upgrade);
+                quote! {{
+                    extern "C" fn nif_upgrade(
+                        env: rustler::codegen_runtime::NIF_ENV,
+                        priv_data: *mut *mut rustler::codegen_runtime::c_void,
+                        old_priv_data: *mut *mut rustler::codegen_runtime::c_void,
+                        load_info: rustler::codegen_runtime::NIF_TERM
+                    ) -> rustler::codegen_runtime::c_int {
+                        unsafe {
+                            rustler::codegen_runtime::handle_nif_upgrade_call(
+                                #upgrade,
+                                #resources,
+                                env,
+                                priv_data,
+                                old_priv_data,
+                                load_info
+                            )
+                        }
+                    }
+                    Some(nif_upgrade)
+                }}
+            }
+            None => quote!(None),
+        };
+
+        let unload = match input.unload {
+            Some(unload) => quote!(Some(#unload)),
+            None => quote!(None),
+        };
 
         let inner = quote! {
             static mut NIF_ENTRY: Option<rustler::codegen_runtime::DEF_NIF_ENTRY> = None;
@@ -72,19 +158,34 @@ impl From<InitMacroInput> for proc_macro2::TokenStream {
                 load: {
                     extern "C" fn nif_load(
                         env: rustler::codegen_runtime::NIF_ENV,
-                        _priv_data: *mut *mut rustler::codegen_runtime::c_void,
+                        priv_data: *mut *mut rustler::codegen_runtime::c_void,
                         load_info: rustler::codegen_runtime::NIF_TERM
                     ) -> rustler::codegen_runtime::c_int {
                         unsafe {
-                            // TODO: If an unwrap ever happens, we will unwind right into C! Fix this!
-                            rustler::codegen_runtime::handle_nif_init_call(#load, env, load_info)
+                            rustler::codegen_runtime::handle_nif_init_call(
+                                #load,
+                                #resources,
+                                env,
+                                priv_data,
+                                load_info
+                            )
                         }
                     }
                     Some(nif_load)
                 },
                 reload: None,
-                upgrade: None,
-                unload: None,
+                upgrade: #upgrade,
+                unload: {
+                    extern "C" fn nif_unload(
+                        env: rustler::codegen_runtime::NIF_ENV,
+                        priv_data: *mut rustler::codegen_runtime::c_void
+                    ) {
+                        unsafe {
+                            rustler::codegen_runtime::handle_nif_unload_call(#unload, env, priv_data)
+                        }
+                    }
+                    Some(nif_unload)
+                },
                 vm_variant: b"beam.vanilla\0".as_ptr() as *const rustler::codegen_runtime::c_char,
                 options: 0,
                 sizeof_ErlNifResourceTypeInit: rustler::codegen_runtime::get_nif_resource_type_init_size(),
@@ -116,6 +217,42 @@ impl From<InitMacroInput> for proc_macro2::TokenStream {
     }
 }
 
+/// Wrap a `load` function, so that the load info is decoded into the type of its second argument.
+fn load_callback(function: &Expr) -> TokenStream {
+    quote! {
+        Some({
+            fn nif_load_callback<'a>(
+                env: rustler::Env<'a>,
+                load_info: rustler::Term<'a>
+            ) -> Result<
+                Option<rustler::codegen_runtime::PrivData>,
+                rustler::codegen_runtime::LoadError
+            > {
+                rustler::codegen_runtime::call_load(env, load_info, #function)
+            }
+            nif_load_callback as rustler::codegen_runtime::LoadCallback
+        })
+    }
+}
+
+/// Wrap an `upgrade` function, so that the old private data is downcast to the type of its second
+/// argument and the load info is decoded into the type of its third argument.
+fn upgrade_callback(function: &Expr) -> TokenStream {
+    quote! {{
+        fn nif_upgrade_callback<'a>(
+            env: rustler::Env<'a>,
+            old_priv_data: rustler::OldPrivData<'a>,
+            load_info: rustler::Term<'a>
+        ) -> Result<
+            Option<rustler::codegen_runtime::PrivData>,
+            rustler::codegen_runtime::LoadError
+        > {
+            rustler::codegen_runtime::call_upgrade(env, old_priv_data, load_info, #function)
+        }
+        nif_upgrade_callback as rustler::codegen_runtime::UpgradeCallback
+    }}
+}
+
 fn nif_funcs(funcs: Punctuated<Expr, Comma>) -> TokenStream {
     let mut tokens = TokenStream::new();
 
@@ -52,6 +52,82 @@ enum RustlerAttr {
 ///
 /// rustler::init!("Elixir.Math", [add, sub, mul, div], load = load);
 /// ```
+///
+/// The second argument of `load` is the `load_data` configured in `use Rustler`. It can be taken
+/// as a `Term` or as any type that implements `Decoder`; if decoding fails, loading the library
+/// fails.
+///
+/// `load` returns either a `bool` that indicates whether loading succeeded, an `Option<T>` or a
+/// `NifResult<T>`. In the latter cases, the successful value becomes the private data of the
+/// library, which can be retrieved with `Env::priv_data::<T>()` in every NIF call and is dropped
+/// when the library is unloaded. `T` has to be `Send + Sync`, as NIFs may run concurrently.
+/// The error of a `NifResult` is reported through the VM's `logger`.
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// struct LoadInfo {
+///     path: String,
+/// }
+///
+/// struct Config {
+///     path: String,
+/// }
+///
+/// fn load(env: Env, info: LoadInfo) -> NifResult<Config> {
+///     Ok(Config { path: info.path })
+/// }
+///
+/// #[rustler::nif]
+/// fn path<'a>(env: Env<'a>) -> &'a str {
+///     &env.priv_data::<Config>().unwrap().path
+/// }
+/// ```
+///
+/// If loading fails, `erlang:load_nif/2` returns an error that contains a return code: `1` if
+/// `load` failed, `2` if it panicked, `3` if a resource type could not be registered and `4` if
+/// the load data could not be decoded.
+///
+/// Resource types can be listed in the `resources` option. They are registered with
+/// `Env::register` when the library is loaded, before `load` is called. If a type cannot be
+/// registered, an error naming the type is logged and loading the library fails.
+///
+/// ```ignore
+/// struct Counter(AtomicUsize);
+///
+/// impl rustler::Resource for Counter {}
+///
+/// rustler::init!("Elixir.Counter", [new, increment], resources = [Counter]);
+/// ```
+///
+/// To support hot code upgrades, pass an `upgrade` function. It is called instead of `load` when
+/// the module is reloaded while an older version of the library is still in use, after the listed
+/// resource types have been taken over from the old library. It takes the private data of the old
+/// library as a `rustler::OldPrivData`, followed by the load data, and returns the same types as
+/// `load`. The old private data can only be downcast to a Rust type if the old library was loaded
+/// from the same copy of the library code, see `OldPrivData` for carrying it over to a new build.
+/// The result of `upgrade` becomes the private data of the new library. Without it, the VM
+/// refuses to upgrade the library. An `unload` function (`fn(Env)`) is called when the code of the module is purged,
+/// before the private data is dropped.
+///
+/// ```ignore
+/// fn upgrade(env: Env, old_config: OldPrivData, info: LoadInfo) -> NifResult<Config> {
+///     let path = match old_config.downcast_ref::<Config>() {
+///         Some(config) => config.path.clone(),
+///         None => info.path,
+///     };
+///     Ok(Config { path })
+/// }
+///
+/// fn unload(env: Env) {}
+///
+/// rustler::init!(
+///     "Elixir.Counter",
+///     [new, increment],
+///     resources = [Counter],
+///     upgrade = upgrade,
+///     unload = unload
+/// );
+/// ```
 #[proc_macro]
 pub fn init(input: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(input as init::InitMacroInput);
@@ -89,6 +165,36 @@ pub fn init(input: TokenStream) -> TokenStream {
 ///     42
 /// }
 /// ```
+///
+/// Long-running work can also be split into steps with the `yielding` flag. The last argument of a
+/// yielding NIF is its state, which is `None` on the first call. Returning `Yield(state)` gives
+/// control back to the scheduler and calls the NIF again later with `Some(state)`, until it
+/// returns `Done(value)`. Returning `Reschedule(scheduler, state)` continues on another scheduler,
+/// e.g. on a dirty scheduler for large inputs. See `rustler::schedule::Yielding`.
+///
+/// ```ignore
+/// #[nif(yielding)]
+/// fn count_to(env: Env, n: u64, state: Option<u64>) -> Yielding<u64, u64> {
+///     let mut i = state.unwrap_or(0);
+///     while i < n {
+///         i += 1;
+///         if consume_timeslice(env, 1) {
+///             return Yield(i);
+///         }
+///     }
+///     Done(i)
+/// }
+/// ```
+///
+/// An `async fn` returns a reference right away and runs the future on a separate thread, see
+/// `rustler::task`. When the future completes, `{ref, result}` is sent to the calling process.
+///
+/// ```ignore
+/// #[nif]
+/// async fn fetch(url: String) -> String {
+///     client().get(&url).await
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn nif(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut nif_attributes = nif::NifAttributes::default();
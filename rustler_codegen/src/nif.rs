@@ -1,7 +1,8 @@
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::meta::ParseNestedMeta;
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::token::Comma;
 use syn::LitStr;
 
@@ -11,6 +12,7 @@ const VALID_SCHEDULE_OPTIONS: [&str; 3] = ["Normal", "DirtyCpu", "DirtyIo"];
 pub struct NifAttributes {
     schedule: Option<LitStr>,
     custom_name: Option<LitStr>,
+    yielding: bool,
 }
 
 impl NifAttributes {
@@ -30,8 +32,12 @@ impl NifAttributes {
         } else if meta.path.is_ident("name") {
             self.custom_name = Some(meta.value()?.parse()?);
             Ok(())
+        } else if meta.path.is_ident("yielding") {
+            self.yielding = true;
+            Ok(())
         } else {
-            Err(meta.error("Unsupported nif macro attribute. Expecting schedule or name."))
+            Err(meta
+                .error("Unsupported nif macro attribute. Expecting schedule, name or yielding."))
         }
     }
 }
@@ -39,13 +45,27 @@ impl NifAttributes {
 pub fn transcoder_decorator(nif_attributes: NifAttributes, fun: syn::ItemFn) -> TokenStream {
     let sig = &fun.sig;
     let name = &sig.ident;
-    let inputs = &sig.inputs;
+    let mut inputs = sig.inputs.clone();
+
+    // The last argument of a yielding NIF is its state, which is not passed from Erlang.
+    let yield_state = if nif_attributes.yielding {
+        match inputs.pop().map(|pair| pair.into_value()) {
+            Some(syn::FnArg::Typed(state)) => Some(state),
+            _ => panic!("A yielding NIF must take its state as the last argument"),
+        }
+    } else {
+        None
+    };
+
+    // Yielding NIFs and folds keep their state in a `YieldState` resource between calls, which
+    // `rustler::init!` only registers if it is needed.
+    let uses_yield_state = yield_state.is_some() || returns_fold(&sig.output);
 
     let flags = schedule_flag(nif_attributes.schedule);
     let function = fun.to_owned().into_token_stream();
     let arity = arity(inputs.clone());
     let decoded_terms = extract_inputs(inputs.clone());
-    let argument_names = create_function_params(inputs.clone());
+    let argument_names = create_function_params(sig.inputs.clone());
     let erl_func_name = nif_attributes
         .custom_name
         .map_or_else(|| name.to_string(), |n| n.value().to_string());
@@ -54,6 +74,72 @@ pub fn transcoder_decorator(nif_attributes: NifAttributes, fun: syn::ItemFn) ->
         panic!("Only non-Control ASCII strings are supported as function names");
     }
 
+    let call = if sig.asyncness.is_some() {
+        quote! { rustler::task::Task::new(#name(#argument_names)) }
+    } else {
+        quote! { #name(#argument_names) }
+    };
+
+    let wrapper = match yield_state {
+        Some(state) => {
+            let state_name = &state.pat;
+            let state_type = &state.ty;
+            // Checks at compile time that the state argument is `Option<S>` for the `S` of the
+            // returned `Yielding<S, T>`, rather than failing to downcast it at runtime.
+            let state_marker = quote_spanned! { state_type.span() =>
+                std::marker::PhantomData::<#state_type>
+            };
+
+            quote! {
+                fn wrapper<'a>(
+                    env: rustler::Env<'a>,
+                    args: &[rustler::Term<'a>]
+                ) -> rustler::codegen_runtime::NifReturned {
+                    let result: std::thread::Result<_> = std::panic::catch_unwind(move || {
+                        #decoded_terms
+                        let #state_name: #state_type = match rustler::codegen_runtime::take_yield_state(args, #arity as usize) {
+                            Ok(value) => value,
+                            Err(err) => return Err(err)
+                        };
+                        #function
+                        Ok(#call)
+                    });
+
+                    fn check_state<S, T>(
+                        _result: &std::thread::Result<Result<rustler::schedule::Yielding<S, T>, rustler::Error>>,
+                        _state: std::marker::PhantomData<Option<S>>,
+                    ) {
+                    }
+                    check_state(&result, #state_marker);
+
+                    rustler::codegen_runtime::handle_yielding_result(
+                        result,
+                        env,
+                        args,
+                        #arity as usize,
+                        #erl_func_name,
+                        #flags,
+                        nif_func,
+                    )
+                }
+            }
+        }
+        None => quote! {
+            fn wrapper<'a>(
+                env: rustler::Env<'a>,
+                args: &[rustler::Term<'a>]
+            ) -> rustler::codegen_runtime::NifReturned {
+                let result: std::thread::Result<_> = std::panic::catch_unwind(move || {
+                    #decoded_terms
+                    #function
+                    Ok(#call)
+                });
+
+                rustler::codegen_runtime::handle_nif_result(result, env)
+            }
+        },
+    };
+
     quote! {
         #[allow(non_camel_case_types)]
         pub struct #name;
@@ -62,6 +148,7 @@ pub fn transcoder_decorator(nif_attributes: NifAttributes, fun: syn::ItemFn) ->
             const NAME: *const rustler::codegen_runtime::c_char = concat!(#erl_func_name, "\0").as_ptr() as *const rustler::codegen_runtime::c_char;
             const ARITY: rustler::codegen_runtime::c_uint = #arity;
             const FLAGS: rustler::codegen_runtime::c_uint = #flags as rustler::codegen_runtime::c_uint;
+            const USES_YIELD_STATE: bool = #uses_yield_state;
             const RAW_FUNC: unsafe extern "C" fn(
                 nif_env: rustler::codegen_runtime::NIF_ENV,
                 argc: rustler::codegen_runtime::c_int,
@@ -80,18 +167,7 @@ pub fn transcoder_decorator(nif_attributes: NifAttributes, fun: syn::ItemFn) ->
                         .map(|term| rustler::Term::new(env, *term))
                         .collect::<Vec<rustler::Term>>();
 
-                    fn wrapper<'a>(
-                        env: rustler::Env<'a>,
-                        args: &[rustler::Term<'a>]
-                    ) -> rustler::codegen_runtime::NifReturned {
-                        let result: std::thread::Result<_> = std::panic::catch_unwind(move || {
-                            #decoded_terms
-                            #function
-                            Ok(#name(#argument_names))
-                        });
-
-                        rustler::codegen_runtime::handle_nif_result(result, env)
-                    }
+                    #wrapper
                     wrapper(env, &terms).apply(env)
                 }
                 nif_func
@@ -106,6 +182,19 @@ pub fn transcoder_decorator(nif_attributes: NifAttributes, fun: syn::ItemFn) ->
     }
 }
 
+/// Whether the return type names a `ListFold` or `MapFold`, see `rustler::schedule::fold`.
+fn returns_fold(output: &syn::ReturnType) -> bool {
+    fn contains_fold(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "ListFold" || ident == "MapFold",
+            TokenTree::Group(group) => contains_fold(group.stream()),
+            _ => false,
+        })
+    }
+
+    contains_fold(output.to_token_stream())
+}
+
 fn schedule_flag(schedule: Option<LitStr>) -> TokenStream {
     let mut tokens = TokenStream::new();
 
@@ -0,0 +1,7 @@
+use rustler_codegen::init;
+
+struct MyResource;
+
+init!("Elixir.Test", [], resources = MyResource);
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rustler_codegen::nif;
+
+#[nif(yielding)]
+fn count() -> i64 {
+    42
+}
+
+fn main() {}
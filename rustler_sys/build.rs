@@ -374,67 +374,68 @@ fn build_api(b: &mut dyn ApiBuilder, opts: &GenerateOptions) {
     );
     b.func("ERL_NIF_TERM", "enif_make_ref", "env: *mut ErlNifEnv");
 
-    // Skip threading API for now (perhaps forever)
-    // If anybody has a situation where they want to use this API instead of the very fine
-    // Rust API, please tell me.
-    //      Func("*mut ErlNifMutex", "enif_mutex_create", "name: *mut c_uchar"),
-    //      Func("", "enif_mutex_destroy", "mtx: *mut ErlNifMutex"),
-    //      Func("c_int", "enif_mutex_trylock", "mtx: *mut ErlNifMutex"),
-    //      Func("", "enif_mutex_lock", "mtx: *mut ErlNifMutex"),
-    //      Func("", "enif_mutex_unlock", "mtx: *mut ErlNifMutex"),
-    //      Func("*mut ErlNifCond", "enif_cond_create", "name: *mut c_uchar"),
-    //      Func("", "enif_cond_destroy", "cnd: *mut ErlNifCond"),
-    //      Func("", "enif_cond_signal", "cnd: *mut ErlNifCond"),
-    //      Func("", "enif_cond_broadcast", "cnd: *mut ErlNifCond"),
-    //      Func("", "enif_cond_wait", "cnd: *mut ErlNifCond, mtx: *mut ErlNifMutex"),
-    //      Func("*mut ErlNifRWLock", "enif_rwlock_create", "name: *mut c_uchar"),
-    //      Func("", "enif_rwlock_destroy", "rwlck: *mut ErlNifRWLock"),
-    //      Func("c_int", "enif_rwlock_tryrlock", "rwlck: *mut ErlNifRWLock"),
-    //      Func("", "enif_rwlock_rlock", "rwlck: *mut ErlNifRWLock"),
-    //      Func("", "enif_rwlock_runlock", "rwlck: *mut ErlNifRWLock"),
-    //      Func("c_int", "enif_rwlock_tryrwlock", "rwlck: *mut ErlNifRWLock"),
-    //      Func("", "enif_rwlock_rwlock", "rwlck: *mut ErlNifRWLock"),
-    //      Func("", "enif_rwlock_rwunlock", "rwlck: *mut ErlNifRWLock"),
-    //      Func("c_int", "enif_tsd_key_create", "name: *mut c_uchar, key: *mut ErlNifTSDKey"),
-    //      Func("", "enif_tsd_key_destroy", "key: ErlNifTSDKey"),
-    //      Func("", "enif_tsd_set", "key: ErlNifTSDKey, data: *mut c_void"),
-    //      Func("*mut c_void", "enif_tsd_get", "key: ErlNifTSDKey"),
-    //      Func("*mut ErlNifThreadOpts", "enif_thread_opts_create", "name: *mut c_uchar"),
-    //      Func("", "enif_thread_opts_destroy", "opts: *mut ErlNifThreadOpts"),
-    //      Func("c_int", "enif_thread_create", "name: *mut c_uchar, tid: *mut ErlNifTid, func: Option<unsafe extern \"C\" fn (arg1: *mut c_void) -> *mut c_void>, args: *mut c_void, opts: *mut ErlNifThreadOpts"),
-    //      Func("ErlNifTid", "enif_thread_self", ""),
-    //      Func("c_int", "enif_equal_tids", "tid1: ErlNifTid, tid2: ErlNifTid"),
-    //      Func("", "enif_thread_exit", "resp: *mut c_void"),
-    //      Func("c_int", "enif_thread_join", "arg1: ErlNifTid, respp: *mut *mut c_void"),
-    b.dummy("dummy_enif_mutex_create");
-    b.dummy("dummy_enif_mutex_destroy");
-    b.dummy("dummy_enif_mutex_trylock");
-    b.dummy("dummy_enif_mutex_lock");
-    b.dummy("dummy_enif_mutex_unlock");
-    b.dummy("dummy_enif_cond_create");
-    b.dummy("dummy_enif_cond_destroy");
-    b.dummy("dummy_enif_cond_signal");
-    b.dummy("dummy_enif_cond_broadcast");
-    b.dummy("dummy_enif_cond_wait");
-    b.dummy("dummy_enif_rwlock_create");
-    b.dummy("dummy_enif_rwlock_destroy");
-    b.dummy("dummy_enif_rwlock_tryrlock");
-    b.dummy("dummy_enif_rwlock_rlock");
-    b.dummy("dummy_enif_rwlock_runlock");
-    b.dummy("dummy_enif_rwlock_tryrwlock");
-    b.dummy("dummy_enif_rwlock_rwlock");
-    b.dummy("dummy_enif_rwlock_rwunlock");
-    b.dummy("dummy_enif_tsd_key_create");
-    b.dummy("dummy_enif_tsd_key_destroy");
-    b.dummy("dummy_enif_tsd_set");
-    b.dummy("dummy_enif_tsd_get");
-    b.dummy("dummy_enif_thread_opts_create");
-    b.dummy("dummy_enif_thread_opts_destroy");
-    b.dummy("dummy_enif_thread_create");
-    b.dummy("dummy_enif_thread_self");
-    b.dummy("dummy_enif_equal_tids");
-    b.dummy("dummy_enif_thread_exit");
-    b.dummy("dummy_enif_thread_join");
+    b.func("*mut ErlNifMutex", "enif_mutex_create", "name: *mut c_char");
+    b.func("", "enif_mutex_destroy", "mtx: *mut ErlNifMutex");
+    b.func("c_int", "enif_mutex_trylock", "mtx: *mut ErlNifMutex");
+    b.func("", "enif_mutex_lock", "mtx: *mut ErlNifMutex");
+    b.func("", "enif_mutex_unlock", "mtx: *mut ErlNifMutex");
+    b.func("*mut ErlNifCond", "enif_cond_create", "name: *mut c_char");
+    b.func("", "enif_cond_destroy", "cnd: *mut ErlNifCond");
+    b.func("", "enif_cond_signal", "cnd: *mut ErlNifCond");
+    b.func("", "enif_cond_broadcast", "cnd: *mut ErlNifCond");
+    b.func(
+        "",
+        "enif_cond_wait",
+        "cnd: *mut ErlNifCond, mtx: *mut ErlNifMutex",
+    );
+    b.func(
+        "*mut ErlNifRWLock",
+        "enif_rwlock_create",
+        "name: *mut c_char",
+    );
+    b.func("", "enif_rwlock_destroy", "rwlck: *mut ErlNifRWLock");
+    b.func("c_int", "enif_rwlock_tryrlock", "rwlck: *mut ErlNifRWLock");
+    b.func("", "enif_rwlock_rlock", "rwlck: *mut ErlNifRWLock");
+    b.func("", "enif_rwlock_runlock", "rwlck: *mut ErlNifRWLock");
+    b.func("c_int", "enif_rwlock_tryrwlock", "rwlck: *mut ErlNifRWLock");
+    b.func("", "enif_rwlock_rwlock", "rwlck: *mut ErlNifRWLock");
+    b.func("", "enif_rwlock_rwunlock", "rwlck: *mut ErlNifRWLock");
+
+    b.func(
+        "c_int",
+        "enif_tsd_key_create",
+        "name: *mut c_char, key: *mut ErlNifTSDKey",
+    );
+    b.func("", "enif_tsd_key_destroy", "key: ErlNifTSDKey");
+    b.func("", "enif_tsd_set", "key: ErlNifTSDKey, data: *mut c_void");
+    b.func("*mut c_void", "enif_tsd_get", "key: ErlNifTSDKey");
+    b.func(
+        "*mut ErlNifThreadOpts",
+        "enif_thread_opts_create",
+        "name: *mut c_char",
+    );
+    b.func(
+        "",
+        "enif_thread_opts_destroy",
+        "opts: *mut ErlNifThreadOpts",
+    );
+    b.func(
+        "c_int",
+        "enif_thread_create",
+        "name: *mut c_char, tid: *mut ErlNifTid, func: Option<ErlNifThreadFunc>, args: *mut c_void, opts: *mut ErlNifThreadOpts",
+    );
+    b.func("ErlNifTid", "enif_thread_self", "");
+    b.func(
+        "c_int",
+        "enif_equal_tids",
+        "tid1: ErlNifTid, tid2: ErlNifTid",
+    );
+    b.func("", "enif_thread_exit", "resp: *mut c_void");
+    b.func(
+        "c_int",
+        "enif_thread_join",
+        "tid: ErlNifTid, respp: *mut *mut c_void",
+    );
 
     b.func(
         "*mut c_void",
@@ -796,43 +797,47 @@ fn build_api(b: &mut dyn ApiBuilder, opts: &GenerateOptions) {
     }
 
     if opts.nif_version >= (2, 13) {
-        // Skip iovec API for now (perhaps forever).
-        // Consider safer Rust iovec crates like https://crates.io/crates/iovec instead of this API.
-        // If anybody really does need this API in Rust, please file a bug.
-        // Func("ErlNifIOQueue *",  "enif_ioq_create",     "ErlNifIOQueueOpts opts"),
-        // Func("void",             "enif_ioq_destroy",    "ErlNifIOQueue *q"),
-        // Func("int",              "enif_ioq_enq_binary", "ErlNifIOQueue *q, ErlNifBinary *bin, size_t skip"),
-        // Func("int",              "enif_ioq_enqv",       "ErlNifIOQueue *q, ErlNifIOVec *iov, size_t skip"),
-        // Func("size_t",           "enif_ioq_size",       "ErlNifIOQueue *q"),
-        // Func("int",              "enif_ioq_deq",        "ErlNifIOQueue *q, size_t count, size_t *size"),
-        // Func("SysIOVec*",        "enif_ioq_peek",       "ErlNifIOQueue *q, int *iovlen"),
-        // Func("int",              "enif_inspect_iovec",  "ErlNifEnv *env, size_t max_length, ERL_NIF_TERM iovec_term, ERL_NIF_TERM *tail, ErlNifIOVec **iovec"),
-        // Func("void",             "enif_free_iovec",     "ErlNifIOVec *iov")
-        b.dummy("dummy_enif_ioq_create");
-        b.dummy("dummy_enif_ioq_destroy");
-        b.dummy("dummy_enif_ioq_enq_binary");
-        b.dummy("dummy_enif_ioq_enqv");
-        b.dummy("dummy_enif_ioq_size");
-        b.dummy("dummy_enif_ioq_deq");
-        b.dummy("dummy_enif_ioq_peek");
-        b.dummy("dummy_enif_inspect_iovec");
-        b.dummy("dummy_enif_free_iovec");
+        b.func(
+            "*mut ErlNifIOQueue",
+            "enif_ioq_create",
+            "opts: ErlNifIOQueueOpts",
+        );
+        b.func("", "enif_ioq_destroy", "q: *mut ErlNifIOQueue");
+        b.func(
+            "c_int",
+            "enif_ioq_enq_binary",
+            "q: *mut ErlNifIOQueue, bin: *mut ErlNifBinary, skip: size_t",
+        );
+        b.func(
+            "c_int",
+            "enif_ioq_enqv",
+            "q: *mut ErlNifIOQueue, iov: *mut ErlNifIOVec, skip: size_t",
+        );
+        b.func("size_t", "enif_ioq_size", "q: *mut ErlNifIOQueue");
+        b.func(
+            "c_int",
+            "enif_ioq_deq",
+            "q: *mut ErlNifIOQueue, count: size_t, size: *mut size_t",
+        );
+        b.func(
+            "*mut SysIOVec",
+            "enif_ioq_peek",
+            "q: *mut ErlNifIOQueue, iovlen: *mut c_int",
+        );
+        b.func("c_int", "enif_inspect_iovec", "env: *mut ErlNifEnv, max_length: size_t, iovec_term: ERL_NIF_TERM, tail: *mut ERL_NIF_TERM, iovec: *mut *mut ErlNifIOVec");
+        b.func("", "enif_free_iovec", "iov: *mut ErlNifIOVec");
     }
 
     if opts.nif_version >= (2, 14) {
-        // Skip iovec and synchronization APIs for now (perhaps forever).
-        // Consider safer Rust iovec crates like https://crates.io/crates/iovec instead of this API.
-        // If anybody really does need this API in Rust, please file a bug.
-        // Func("int",  "enif_ioq_peek_head",        "ErlNifEnv *env, ErlNifIOQueue *q, size_t *size, ERL_NIF_TERM *head"),
-        // Func("char*, "enif_mutex_name",           "ErlNifMutex*"),
-        // Func("char*, "enif_cond_name",            "ErlNifCond*"),
-        // Func("char*, "enif_rwlock_name",          "ErlNifRWLock*"),
-        // Func("char*, "enif_thread_name",          "ErlNifTid"),
-        b.dummy("dummy_enif_ioq_peek_head");
-        b.dummy("dummy_enif_mutex_name");
-        b.dummy("dummy_enif_cond_name");
-        b.dummy("dummy_enif_rwlock_name");
-        b.dummy("dummy_enif_thread_name");
+        b.func("c_int", "enif_ioq_peek_head", "env: *mut ErlNifEnv, q: *mut ErlNifIOQueue, size: *mut size_t, head: *mut ERL_NIF_TERM");
+        b.func("*mut c_char", "enif_mutex_name", "mtx: *mut ErlNifMutex");
+        b.func("*mut c_char", "enif_cond_name", "cnd: *mut ErlNifCond");
+        b.func(
+            "*mut c_char",
+            "enif_rwlock_name",
+            "rwlck: *mut ErlNifRWLock",
+        );
+        b.func("*mut c_char", "enif_thread_name", "tid: ErlNifTid");
 
         // See format! and write!
         // Func("int",  "enif_vfprintf",             "FILE*, const char *fmt, va_list"),
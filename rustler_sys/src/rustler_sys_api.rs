@@ -146,11 +146,11 @@ pub type ErlNifResourceDynCall =
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct ErlNifResourceTypeInit {
-    dtor: *const ErlNifResourceDtor,
-    stop: *const ErlNifResourceStop, // at ERL_NIF_SELECT_STOP event
-    down: *const ErlNifResourceDown, // enif_monitor_process
-    members: c_int,
-    dyncall: *const ErlNifResourceDynCall,
+    pub dtor: Option<ErlNifResourceDtor>,
+    pub stop: Option<ErlNifResourceStop>, // at ERL_NIF_SELECT_STOP event
+    pub down: Option<ErlNifResourceDown>, // enif_monitor_process
+    pub members: c_int,
+    pub dyncall: Option<ErlNifResourceDynCall>,
 }
 
 /// See [ErlNifSelectFlags](http://erlang.org/doc/man/erl_nif.html#ErlNifSelectFlags) in the Erlang docs.
@@ -159,6 +159,13 @@ pub type ErlNifSelectFlags = c_int;
 pub const ERL_NIF_SELECT_READ: ErlNifSelectFlags = 1 << 0;
 pub const ERL_NIF_SELECT_WRITE: ErlNifSelectFlags = 1 << 1;
 pub const ERL_NIF_SELECT_STOP: ErlNifSelectFlags = 1 << 2;
+pub const ERL_NIF_SELECT_CANCEL: ErlNifSelectFlags = 1 << 3;
+
+// Bits in the return value of `enif_select()`.
+#[allow(clippy::identity_op)]
+pub const ERL_NIF_SELECT_STOP_CALLED: c_int = 1 << 0;
+pub const ERL_NIF_SELECT_STOP_SCHEDULED: c_int = 1 << 1;
+pub const ERL_NIF_SELECT_INVALID_EVENT: c_int = 1 << 2;
 pub const ERL_NIF_SELECT_FAILED: ErlNifSelectFlags = 1 << 3;
 pub const ERL_NIF_SELECT_READ_CANCELLED: ErlNifSelectFlags = 1 << 4;
 pub const ERL_NIF_SELECT_WRITE_CANCELLED: ErlNifSelectFlags = 1 << 5;
@@ -180,6 +187,9 @@ pub struct ErlNifMonitor {
 pub enum ErlNifResourceFlags {
     ERL_NIF_RT_CREATE = 1,
     ERL_NIF_RT_TAKEOVER = 2,
+    /// `ERL_NIF_RT_CREATE | ERL_NIF_RT_TAKEOVER`: create the resource type if it does not exist
+    /// yet, otherwise take it over.
+    ERL_NIF_RT_CREATE_OR_TAKEOVER = 3,
 }
 
 /// See [ErlNifCharEncoding](http://www.erlang.org/doc/man/erl_nif.html#ErlNifCharEncoding) in the Erlang docs.
@@ -288,6 +298,98 @@ pub struct ErlNifPort {
 }
 // ref https://github.com/erlang/otp/blob/maint/erts/emulator/beam/erl_nif.h#L155
 
+/// Make a port term from an `ErlNifPort`.
+///
+/// The NIF API has no `enif_make_port`; like `enif_make_pid`, this returns the wrapped term.
+pub unsafe fn enif_make_port(_env: *mut ErlNifEnv, port: ErlNifPort) -> ERL_NIF_TERM {
+    port.port_id
+}
+
+/// See [ErlNifMutex](http://erlang.org/doc/man/erl_nif.html#ErlNifMutex) in the Erlang docs.
+#[allow(missing_copy_implementations)]
+#[repr(C)]
+pub struct ErlNifMutex {
+    dummy: c_int,
+}
+
+/// See [ErlNifCond](http://erlang.org/doc/man/erl_nif.html#ErlNifCond) in the Erlang docs.
+#[allow(missing_copy_implementations)]
+#[repr(C)]
+pub struct ErlNifCond {
+    dummy: c_int,
+}
+
+/// See [ErlNifRWLock](http://erlang.org/doc/man/erl_nif.html#ErlNifRWLock) in the Erlang docs.
+#[allow(missing_copy_implementations)]
+#[repr(C)]
+pub struct ErlNifRWLock {
+    dummy: c_int,
+}
+
+/// See [ErlNifTSDKey](http://erlang.org/doc/man/erl_nif.html#ErlNifTSDKey) in the Erlang docs.
+pub type ErlNifTSDKey = c_int;
+
+/// See [ErlNifThreadOpts](http://erlang.org/doc/man/erl_nif.html#ErlNifThreadOpts) in the Erlang docs.
+#[derive(Debug)]
+#[repr(C)]
+pub struct ErlNifThreadOpts {
+    /// Suggested stack size in kilowords.
+    pub suggested_stack_size: c_int,
+}
+
+/// See [ErlNifTid](http://erlang.org/doc/man/erl_nif.html#ErlNifTid) in the Erlang docs.
+pub type ErlNifTid = *mut c_void;
+
+/// Entry point of a thread created with `enif_thread_create`.
+pub type ErlNifThreadFunc = unsafe extern "C" fn(arg: *mut c_void) -> *mut c_void;
+
+/// See [SysIOVec](http://erlang.org/doc/man/erl_nif.html#SysIOVec) in the Erlang docs.
+#[cfg(unix)]
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct SysIOVec {
+    pub iov_base: *mut c_char,
+    pub iov_len: size_t,
+}
+
+/// See [SysIOVec](http://erlang.org/doc/man/erl_nif.html#SysIOVec) in the Erlang docs.
+#[cfg(windows)]
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct SysIOVec {
+    pub iov_len: c_ulong,
+    pub iov_base: *mut c_char,
+}
+
+/// Number of vectors an `ErlNifIOVec` can hold without a separate allocation.
+pub const ERL_NIF_IOVEC_SIZE: usize = 16;
+
+/// See [ErlNifIOVec](http://erlang.org/doc/man/erl_nif.html#ErlNifIOVec) in the Erlang docs.
+#[repr(C)]
+pub struct ErlNifIOVec {
+    pub iovcnt: c_int,
+    pub size: size_t,
+    pub iov: *mut SysIOVec,
+    ref_bins: *mut *mut c_void,
+    flags: c_int,
+    small_iov: [SysIOVec; ERL_NIF_IOVEC_SIZE],
+    small_ref_bin: [*mut c_void; ERL_NIF_IOVEC_SIZE],
+}
+
+/// See [ErlNifIOQueue](http://erlang.org/doc/man/erl_nif.html#ErlNifIOQueue) in the Erlang docs.
+#[allow(missing_copy_implementations)]
+#[repr(C)]
+pub struct ErlNifIOQueue {
+    dummy: c_int,
+}
+
+/// See [ErlNifIOQueueOpts](http://erlang.org/doc/man/erl_nif.html#ErlNifIOQueueOpts) in the Erlang docs.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub enum ErlNifIOQueueOpts {
+    ERL_NIF_IOQ_NORMAL = 1,
+}
+
 /// See [ErlNifBinaryToTerm](http://erlang.org/doc/man/erl_nif.html#ErlNifBinaryToTerm) in the Erlang docs.
 pub type ErlNifBinaryToTerm = c_int;
 pub const ERL_NIF_BIN2TERM_SAFE: ErlNifBinaryToTerm = 0x2000_0000;
@@ -10,6 +10,8 @@ int main() {
 	printf("ErlNifPid %lu\n", sizeof(ErlNifPid));
 	printf("ErlNifSysInfo %lu\n", sizeof(ErlNifSysInfo));
 	printf("ErlNifMapIterator %lu\n", sizeof(ErlNifMapIterator));
+	printf("SysIOVec %lu\n", sizeof(SysIOVec));
+	printf("ErlNifIOVec %lu\n", sizeof(ErlNifIOVec));
 
 	return 0;
 }
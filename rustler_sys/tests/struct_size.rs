@@ -74,6 +74,8 @@ fn test1() {
     ErlNifPid
     ErlNifSysInfo
     ErlNifMapIterator
+    SysIOVec
+    ErlNifIOVec
 
     */
 
@@ -108,4 +110,9 @@ fn test1() {
         &size_of::<ErlNifMapIterator>(),
         sizemap.get("ErlNifMapIterator").unwrap()
     );
+    assert_eq!(&size_of::<SysIOVec>(), sizemap.get("SysIOVec").unwrap());
+    assert_eq!(
+        &size_of::<ErlNifIOVec>(),
+        sizemap.get("ErlNifIOVec").unwrap()
+    );
 }
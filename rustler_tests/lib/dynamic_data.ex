@@ -11,4 +11,5 @@ defmodule DynamicData do
     load_data_fun: {DynamicData.Config, :nif_data}
 
   def get_dataset, do: :erlang.nif_error(:nif_not_loaded)
+  def owned_env_has_dataset, do: :erlang.nif_error(:nif_not_loaded)
 end
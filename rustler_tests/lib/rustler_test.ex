@@ -66,6 +66,21 @@ defmodule RustlerTest do
 
   def resource_make_with_binaries(), do: err()
   def resource_make_binaries(_), do: err()
+  def resource_make_iodata(_), do: err()
+
+  def monitor_resource_make(), do: err()
+  def monitor_resource_make_panicking(), do: err()
+  def monitor_resource_monitor(_, _, _), do: err()
+  def monitor_resource_demonitor(_), do: err()
+  def monitor_resource_down_called(_), do: err()
+  def callback_resource_make(_), do: err()
+  def callback_resource_destructor_calls(), do: err()
+  def callback_resource_dyncall(_, _), do: err()
+
+  def select_resource_make(), do: err()
+  def select_resource_select_read(_, _, _), do: err()
+  def select_resource_write(_), do: err()
+  def select_resource_stop(_), do: err()
 
   def make_shorter_subbinary(_), do: err()
   def parse_integer(_), do: err()
@@ -77,21 +92,67 @@ defmodule RustlerTest do
   def realloc_grow(), do: err()
   def encode_string(), do: err()
   def decode_iolist(_), do: err()
+  def iovec_inspect(_), do: err()
+  def iovec_inspect_with_max(_, _), do: err()
+  def iodata_build(_), do: err()
+  def iodata_len(_), do: err()
+  def bitstring_info(_), do: err()
+  def bitstring_echo(_), do: err()
+  def bitstring_new(_, _), do: err()
+  def bitstring_sub(_, _, _), do: err()
+  def binary_writer_join(_), do: err()
+  def binary_reader_lines(_), do: err()
+  def binary_reader_frame(_), do: err()
 
   def atom_to_string(_), do: err()
   def atom_equals_ok(_), do: err()
   def binary_to_atom(_), do: err()
   def binary_to_existing_atom(_), do: err()
 
+  def io_queue_new(), do: err()
+  def io_queue_push(_, _), do: err()
+  def io_queue_push_owned(_, _), do: err()
+  def io_queue_chunks(_), do: err()
+  def io_queue_peek_head(_), do: err()
+  def io_queue_dequeue(_, _), do: err()
+  def io_queue_read(_, _), do: err()
+  def io_queue_read_line(_), do: err()
+
   def threaded_fac(_), do: err()
   def threaded_sleep(_), do: err()
+  def erlang_threaded_fac(_), do: err()
+  def thread_builder_join(_, _), do: err()
+  def pooled_sleep(_), do: err()
+  def pooled_fac(_), do: err()
+  def tsd_key_values(_), do: err()
+
+  def task_sleep(_), do: err()
+  def task_panic(), do: err()
+  def task_pooled_add(_, _), do: err()
+  def task_pooled_sleep(_), do: err()
+  def task_spawn(_), do: err()
+
+  def sync_mutex_counter(_, _), do: err()
+  def sync_mutex_try_lock(), do: err()
+  def sync_rwlock(), do: err()
+  def sync_condvar(), do: err()
+  def sync_names(), do: err()
 
   def send_all(_, _), do: err()
   def send(_, _), do: err()
   def whereis_pid(_), do: err()
   def is_process_alive(_), do: err()
+  def whereis_port(_), do: err()
+  def is_port_alive(_), do: err()
+  def port_command(_, _), do: err()
   def sublists(_), do: err()
 
+  def make_ref(), do: err()
+  def reference_echo(_), do: err()
+  def reference_reply_from_thread(_, _), do: err()
+  def reference_store(_), do: err()
+  def reference_take(_), do: err()
+
   def tuple_echo(_), do: err()
   def record_echo(_), do: err()
   def map_echo(_), do: err()
@@ -133,4 +194,27 @@ defmodule RustlerTest do
   def maybe_add_one_to_tuple(_tuple), do: err()
   def add_i32_from_tuple(_tuple), do: err()
   def greeting_person_from_tuple(_tuple), do: err()
+
+  def system_info_fields(), do: err()
+  def system_getenv(_), do: err()
+
+  def time_monotonic(_), do: err()
+  def time_system(_), do: err()
+  def time_offset(_), do: err()
+  def time_convert(_, _, _), do: err()
+  def time_unit_echo(_), do: err()
+  def time_now(), do: err()
+  def time_cpu(), do: err()
+  def time_instant_elapsed(_, _), do: err()
+
+  def yielding_sum(_), do: err()
+  def yielding_steps(_), do: err()
+  def yielding_switch_scheduler(_), do: err()
+  def fold_sum_list(_), do: err()
+  def fold_decode_list(_), do: err()
+  def fold_sum_map(_), do: err()
+
+  def upgrade_calls(), do: err()
+  def unload_calls(), do: err()
+  def library_generation(), do: err()
 end
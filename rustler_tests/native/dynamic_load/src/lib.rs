@@ -1,40 +1,47 @@
-use rustler::Atom;
+use rustler::env::OwnedEnv;
+use rustler::{Binary, Env, Error, NifMap, NifResult};
 use std::{ffi::OsStr, fs::read_to_string, path::PathBuf};
 
-static mut DATASET: Option<Box<str>> = None;
+/// Private data of the library, read once in `load`.
+struct Dataset {
+    data: Box<str>,
+}
+
+/// The `load_data` returned from `DynamicData.Config.nif_data/0`.
+#[derive(NifMap)]
+struct LoadInfo<'a> {
+    priv_path: Binary<'a>,
+}
 
-fn initialize_dataset(mut asset_path: PathBuf) {
+fn initialize_dataset(mut asset_path: PathBuf) -> NifResult<Dataset> {
     asset_path.push("demo_dataset.txt");
 
     // https://github.com/elixir-lsp/elixir-ls/issues/604
     // eprintln!("Loading dataset from {:?}.", &asset_path);
 
-    let data = read_to_string(asset_path).unwrap().into_boxed_str();
-    let data = Some(data);
-
-    // Safety: assumes that this function is being called once when
-    // dynamically loading this library.
-    // `load()` is being called exactly once and OTP will not allow any other function call
-    // before this function returns
-    unsafe { DATASET = data };
+    let data = read_to_string(&asset_path)
+        .map_err(|err| Error::Term(Box::new(format!("{:?}: {}", asset_path, err))))?
+        .into_boxed_str();
+    Ok(Dataset { data })
 }
 
 #[rustler::nif]
-fn get_dataset() -> &'static str {
-    // Safety: see `initialize_dataset()`
-    unsafe { DATASET.as_ref() }.expect("Dataset is not initialized")
+fn get_dataset<'a>(env: Env<'a>) -> &'a str {
+    let dataset = env
+        .priv_data::<Dataset>()
+        .expect("Dataset is not initialized");
+    &dataset.data
 }
 
-fn load<'a>(env: rustler::Env<'a>, args: rustler::Term<'a>) -> bool {
-    let key = Atom::from_str(env, "priv_path").unwrap().to_term(env);
-    let priv_path = args.map_get(key).unwrap();
-    let priv_path = priv_path.into_binary().unwrap().as_slice();
-
-    let asset_path = build_path_buf(priv_path);
+#[rustler::nif]
+fn owned_env_has_dataset() -> bool {
+    OwnedEnv::new().run(|env| env.priv_data::<Dataset>().is_some())
+}
 
-    initialize_dataset(asset_path);
+fn load<'a>(_env: Env<'a>, info: LoadInfo<'a>) -> NifResult<Dataset> {
+    let asset_path = build_path_buf(info.priv_path.as_slice());
 
-    true
+    initialize_dataset(asset_path)
 }
 
 #[cfg(unix)]
@@ -52,4 +59,8 @@ fn build_path_buf(priv_path: &[u8]) -> PathBuf {
     PathBuf::from(priv_path)
 }
 
-rustler::init!("Elixir.DynamicData", [get_dataset], load = load);
+rustler::init!(
+    "Elixir.DynamicData",
+    [get_dataset, owned_env_has_dataset],
+    load = load
+);
@@ -4,15 +4,24 @@ mod test_codegen;
 mod test_dirty;
 mod test_env;
 mod test_error;
+mod test_io_queue;
 mod test_list;
 mod test_map;
 mod test_nif_attrs;
 mod test_primitives;
 mod test_range;
+mod test_reference;
 mod test_resource;
+mod test_select;
+mod test_sync;
+mod test_system;
+mod test_task;
 mod test_term;
 mod test_thread;
+mod test_time;
 mod test_tuple;
+mod test_upgrade;
+mod test_yield;
 
 rustler::init!(
     "Elixir.RustlerTest",
@@ -46,6 +55,19 @@ rustler::init!(
         test_resource::resource_immutable_count,
         test_resource::resource_make_with_binaries,
         test_resource::resource_make_binaries,
+        test_resource::resource_make_iodata,
+        test_resource::monitor_resource_make,
+        test_resource::monitor_resource_make_panicking,
+        test_resource::monitor_resource_monitor,
+        test_resource::monitor_resource_demonitor,
+        test_resource::monitor_resource_down_called,
+        test_resource::callback_resource_make,
+        test_resource::callback_resource_destructor_calls,
+        test_resource::callback_resource_dyncall,
+        test_select::select_resource_make,
+        test_select::select_resource_select_read,
+        test_select::select_resource_write,
+        test_select::select_resource_stop,
         test_atom::atom_to_string,
         test_atom::atom_equals_ok,
         test_atom::binary_to_atom,
@@ -60,13 +82,57 @@ rustler::init!(
         test_binary::realloc_grow,
         test_binary::encode_string,
         test_binary::decode_iolist,
+        test_binary::iovec_inspect,
+        test_binary::iovec_inspect_with_max,
+        test_binary::iodata_build,
+        test_binary::iodata_len,
+        test_binary::bitstring_info,
+        test_binary::bitstring_echo,
+        test_binary::bitstring_new,
+        test_binary::bitstring_sub,
+        test_binary::binary_writer_join,
+        test_binary::binary_reader_lines,
+        test_binary::binary_reader_frame,
+        test_io_queue::io_queue_new,
+        test_io_queue::io_queue_push,
+        test_io_queue::io_queue_push_owned,
+        test_io_queue::io_queue_chunks,
+        test_io_queue::io_queue_peek_head,
+        test_io_queue::io_queue_dequeue,
+        test_io_queue::io_queue_read,
+        test_io_queue::io_queue_read_line,
         test_thread::threaded_fac,
         test_thread::threaded_sleep,
+        test_thread::erlang_threaded_fac,
+        test_thread::thread_builder_join,
+        test_thread::pooled_sleep,
+        test_thread::pooled_fac,
+        test_thread::tsd_key_values,
+        test_task::task_sleep,
+        test_task::task_panic,
+        test_task::task_pooled_add,
+        test_task::task_pooled_sleep,
+        test_task::task_spawn,
+        test_sync::sync_mutex_counter,
+        test_sync::sync_mutex_try_lock,
+        test_sync::sync_rwlock,
+        test_sync::sync_condvar,
+        test_sync::sync_names,
+        test_system::system_info_fields,
+        test_system::system_getenv,
         test_env::send_all,
         test_env::send,
         test_env::whereis_pid,
         test_env::is_process_alive,
+        test_env::whereis_port,
+        test_env::is_port_alive,
+        test_env::port_command,
         test_env::sublists,
+        test_reference::make_ref,
+        test_reference::reference_echo,
+        test_reference::reference_reply_from_thread,
+        test_reference::reference_store,
+        test_reference::reference_take,
         test_codegen::tuple_echo,
         test_codegen::record_echo,
         test_codegen::map_echo,
@@ -103,11 +169,35 @@ rustler::init!(
         test_codegen::reserved_keywords::reserved_keywords_type_echo,
         test_codegen::generic_types::generic_struct_echo,
         test_codegen::generic_types::mk_generic_map,
+        test_time::time_monotonic,
+        test_time::time_system,
+        test_time::time_offset,
+        test_time::time_convert,
+        test_time::time_unit_echo,
+        test_time::time_now,
+        test_time::time_cpu,
+        test_time::time_instant_elapsed,
+        test_yield::yielding_sum,
+        test_yield::yielding_steps_impl,
+        test_yield::yielding_switch_scheduler,
+        test_yield::fold_sum_list,
+        test_yield::fold_decode_list,
+        test_yield::fold_sum_map,
+        test_upgrade::upgrade_calls,
+        test_upgrade::unload_calls,
+        test_upgrade::library_generation,
     ],
-    load = load
+    resources = [
+        test_resource::TestResource,
+        test_resource::ImmutableResource,
+        test_resource::WithBinaries,
+        test_resource::TestMonitorResource,
+        test_resource::CallbackResource,
+        test_select::SelectResource,
+        test_io_queue::IoQueueResource,
+        test_reference::PendingRequest,
+    ],
+    load = test_upgrade::load,
+    upgrade = test_upgrade::upgrade,
+    unload = test_upgrade::unload
 );
-
-fn load(env: rustler::Env, _: rustler::Term) -> bool {
-    test_resource::on_load(env);
-    true
-}
@@ -1,8 +1,8 @@
-use std::io::Write;
+use std::io::{BufRead, Read, Write};
 use std::panic;
 
-use rustler::types::binary::{Binary, NewBinary, OwnedBinary};
-use rustler::{Env, Error, NifResult, Term};
+use rustler::types::binary::{Binary, BinaryReader, NewBinary, OwnedBinary, OwnedBinaryWriter};
+use rustler::{Bitstring, Env, Error, IoData, IoVec, NifResult, Term};
 
 #[rustler::nif]
 pub fn make_shorter_subbinary(binary: Binary) -> NifResult<Binary> {
@@ -77,3 +77,105 @@ pub fn encode_string() -> (String, &'static str) {
 pub fn decode_iolist(binary: Term) -> NifResult<Binary> {
     binary.decode_as_binary()
 }
+
+/// Copy `data` into a new binary, shared by the tests that return read data.
+pub fn copy_to_binary<'a>(env: Env<'a>, data: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, data.len());
+    binary.as_mut_slice().copy_from_slice(data);
+    binary.into()
+}
+
+#[rustler::nif]
+pub fn iovec_inspect<'a>(env: Env<'a>, iovec: IoVec) -> (usize, Vec<usize>, Binary<'a>) {
+    let sizes = iovec.chunks().map(|chunk| chunk.len()).collect();
+    (iovec.len(), sizes, copy_to_binary(env, &iovec.to_vec()))
+}
+
+#[rustler::nif]
+pub fn iovec_inspect_with_max<'a>(
+    env: Env<'a>,
+    term: Term<'a>,
+    max: usize,
+) -> NifResult<(Binary<'a>, Option<Term<'a>>)> {
+    let iovec = IoVec::from_term_with_max(term, max)?;
+    Ok((copy_to_binary(env, &iovec.to_vec()), iovec.tail()))
+}
+
+#[rustler::nif]
+pub fn iodata_build<'a>(env: Env<'a>, payload: Binary<'a>) -> NifResult<IoData<'a>> {
+    let mut owned = OwnedBinary::new(3).unwrap();
+    owned.as_mut_slice().copy_from_slice(b"end");
+
+    let mut data = IoData::new(env);
+    data.push_bytes(&(payload.len() as u16).to_be_bytes())
+        .push_bytes(b":")
+        .push_binary(payload)
+        .push_subbinary(payload, 1, 2)?
+        .push_owned(owned);
+    Ok(data)
+}
+
+/// Build iodata out of `parts`, and return its length and the resulting iolist.
+#[rustler::nif]
+pub fn iodata_len<'a>(env: Env<'a>, parts: Vec<Binary<'a>>) -> (usize, Term<'a>) {
+    let mut data = IoData::new(env);
+    for part in parts {
+        data.push_binary(part);
+    }
+    (data.len(), data.into_term())
+}
+
+#[rustler::nif]
+pub fn bitstring_info(bits: Bitstring) -> (usize, u8, bool) {
+    (bits.bit_size(), bits.trailing_bits(), bits.is_binary())
+}
+
+#[rustler::nif]
+pub fn bitstring_echo(bits: Bitstring) -> Bitstring {
+    bits
+}
+
+#[rustler::nif]
+pub fn bitstring_new<'a>(env: Env<'a>, bytes: Binary, bit_size: usize) -> Bitstring<'a> {
+    Bitstring::new(env, &bytes, bit_size)
+}
+
+#[rustler::nif]
+pub fn bitstring_sub(
+    bits: Bitstring,
+    bit_offset: usize,
+    bit_length: usize,
+) -> NifResult<Bitstring> {
+    bits.sub_bitstring(bit_offset, bit_length)
+}
+
+#[rustler::nif]
+pub fn binary_writer_join(env: Env, count: u32) -> (usize, Binary) {
+    let mut writer = OwnedBinaryWriter::new();
+    for i in 0..count {
+        if i > 0 {
+            writer.write_all(b",").unwrap();
+        }
+        write!(writer, "{}", i).unwrap();
+    }
+    let capacity = writer.capacity();
+    (capacity, writer.finish().release(env))
+}
+
+#[rustler::nif]
+pub fn binary_reader_lines(data: Binary) -> Vec<String> {
+    BinaryReader::new(data)
+        .lines()
+        .map(|line| line.unwrap())
+        .collect()
+}
+
+#[rustler::nif]
+pub fn binary_reader_frame(data: Binary) -> NifResult<(Binary, Binary)> {
+    let mut reader = BinaryReader::new(data);
+    let mut length = [0; 2];
+    reader.read_exact(&mut length).map_err(|_| Error::BadArg)?;
+    let payload = reader.read_binary(u16::from_be_bytes(length) as usize)?;
+    let rest = reader.read_binary(reader.remaining().len())?;
+    Ok((payload, rest))
+}
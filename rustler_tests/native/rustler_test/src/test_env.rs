@@ -1,7 +1,7 @@
 use rustler::env::{OwnedEnv, SavedTerm, SendError};
 use rustler::types::atom;
 use rustler::types::list::ListIterator;
-use rustler::types::LocalPid;
+use rustler::types::{LocalPid, LocalPort};
 use rustler::{Atom, Encoder, Env, NifResult, Term};
 use std::thread;
 
@@ -33,6 +33,24 @@ pub fn is_process_alive(env: Env, pid: LocalPid) -> bool {
     env.is_process_alive(pid)
 }
 
+#[rustler::nif]
+pub fn whereis_port<'a>(env: Env<'a>, term: Term<'a>) -> Option<LocalPort> {
+    env.whereis_port(term)
+}
+
+#[rustler::nif]
+pub fn is_port_alive(env: Env, port: LocalPort) -> bool {
+    port.is_alive(env)
+}
+
+#[rustler::nif]
+pub fn port_command<'a>(env: Env<'a>, port: LocalPort, data: Term<'a>) -> Atom {
+    match env.port_command(&port, data) {
+        Ok(()) => atom::ok(),
+        Err(_) => atom::error(),
+    }
+}
+
 #[rustler::nif]
 pub fn sublists<'a>(env: Env<'a>, list: Term<'a>) -> NifResult<Atom> {
     // This is a "threaded NIF": it spawns a thread that sends a message back
@@ -0,0 +1,81 @@
+use std::io::{BufRead, Read};
+use std::sync::Mutex;
+
+use rustler::{Binary, Env, IoQueue, NifResult, OwnedBinary, Resource, ResourceArc, Term};
+
+use crate::test_binary::copy_to_binary;
+
+pub struct IoQueueResource {
+    queue: Mutex<IoQueue>,
+}
+
+impl Resource for IoQueueResource {}
+
+#[rustler::nif]
+pub fn io_queue_new() -> ResourceArc<IoQueueResource> {
+    ResourceArc::new(IoQueueResource {
+        queue: Mutex::new(IoQueue::new()),
+    })
+}
+
+#[rustler::nif]
+pub fn io_queue_push(resource: ResourceArc<IoQueueResource>, data: Term) -> NifResult<usize> {
+    let mut queue = resource.queue.lock().unwrap();
+    queue.push_term(data)?;
+    Ok(queue.len())
+}
+
+#[rustler::nif]
+pub fn io_queue_push_owned(resource: ResourceArc<IoQueueResource>, data: Binary) -> usize {
+    let mut queue = resource.queue.lock().unwrap();
+    queue.push(OwnedBinary::from_unowned(&data).unwrap());
+    queue.len()
+}
+
+#[rustler::nif]
+pub fn io_queue_chunks<'a>(
+    env: Env<'a>,
+    resource: ResourceArc<IoQueueResource>,
+) -> Vec<Binary<'a>> {
+    let queue = resource.queue.lock().unwrap();
+    queue
+        .chunks()
+        .map(|chunk| copy_to_binary(env, chunk))
+        .collect()
+}
+
+#[rustler::nif]
+pub fn io_queue_peek_head<'a>(
+    env: Env<'a>,
+    resource: ResourceArc<IoQueueResource>,
+) -> Option<Binary<'a>> {
+    resource.queue.lock().unwrap().peek_head(env)
+}
+
+#[rustler::nif]
+pub fn io_queue_dequeue(resource: ResourceArc<IoQueueResource>, count: usize) -> bool {
+    resource.queue.lock().unwrap().dequeue(count)
+}
+
+#[rustler::nif]
+pub fn io_queue_read<'a>(
+    env: Env<'a>,
+    resource: ResourceArc<IoQueueResource>,
+    count: usize,
+) -> Binary<'a> {
+    let mut buf = vec![0; count];
+    let read = resource.queue.lock().unwrap().read(&mut buf).unwrap();
+    copy_to_binary(env, &buf[..read])
+}
+
+#[rustler::nif]
+pub fn io_queue_read_line<'a>(env: Env<'a>, resource: ResourceArc<IoQueueResource>) -> Binary<'a> {
+    let mut line = Vec::new();
+    resource
+        .queue
+        .lock()
+        .unwrap()
+        .read_until(b'\n', &mut line)
+        .unwrap();
+    copy_to_binary(env, &line)
+}
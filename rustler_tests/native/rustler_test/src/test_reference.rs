@@ -0,0 +1,46 @@
+use rustler::types::atom;
+use rustler::{Encoder, Env, LocalPid, OwnedEnv, OwnedReference, Reference, Resource, ResourceArc};
+use std::sync::Mutex;
+use std::thread;
+
+/// Keeps the reference of a request until it is answered.
+pub struct PendingRequest {
+    reference: Mutex<Option<OwnedReference>>,
+}
+
+impl Resource for PendingRequest {}
+
+#[rustler::nif]
+pub fn make_ref(env: Env) -> Reference {
+    env.make_ref()
+}
+
+#[rustler::nif]
+pub fn reference_echo(reference: Reference) -> Reference {
+    reference
+}
+
+/// Reply to `pid` from another thread with `{reference, :ok}`, like a `GenServer` reply.
+#[rustler::nif]
+pub fn reference_reply_from_thread(pid: LocalPid, reference: Reference) -> rustler::Atom {
+    let reference = OwnedReference::new(reference);
+
+    thread::spawn(move || {
+        let mut owned_env = OwnedEnv::new();
+        let _ = owned_env.send_and_clear(&pid, |env| (&reference, atom::ok()).encode(env));
+    });
+
+    atom::ok()
+}
+
+#[rustler::nif]
+pub fn reference_store(reference: Reference) -> ResourceArc<PendingRequest> {
+    ResourceArc::new(PendingRequest {
+        reference: Mutex::new(Some(OwnedReference::new(reference))),
+    })
+}
+
+#[rustler::nif]
+pub fn reference_take(request: ResourceArc<PendingRequest>) -> Option<OwnedReference> {
+    request.reference.lock().unwrap().take()
+}
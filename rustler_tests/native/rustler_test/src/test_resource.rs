@@ -1,5 +1,12 @@
-use rustler::{Binary, Env, ResourceArc};
-use std::sync::RwLock;
+use rustler::env::OwnedEnv;
+use rustler::{Atom, Binary, Encoder, Env, IoData, LocalPid, Monitor, Resource, ResourceArc, Term};
+use std::sync::{Mutex, RwLock};
+
+mod atoms {
+    rustler::atoms! {
+        monitor_resource_down,
+    }
+}
 
 pub struct TestResource {
     test_field: RwLock<i32>,
@@ -17,11 +24,79 @@ pub struct WithBinaries {
     b: Vec<u8>,
 }
 
-pub fn on_load(env: Env) -> bool {
-    rustler::resource!(TestResource, env);
-    rustler::resource!(ImmutableResource, env);
-    rustler::resource!(WithBinaries, env);
-    true
+impl Resource for TestResource {}
+impl Resource for ImmutableResource {}
+impl Resource for WithBinaries {}
+
+pub struct TestMonitorResource {
+    inner: Mutex<TestMonitorResourceInner>,
+}
+
+#[derive(Default)]
+struct TestMonitorResourceInner {
+    mon: Option<Monitor>,
+    notify: Option<LocalPid>,
+    down_called: bool,
+    panic_in_down: bool,
+}
+
+impl Resource for TestMonitorResource {
+    const IMPLEMENTS_DOWN: bool = true;
+
+    fn down<'a>(&'a self, _env: Env<'a>, _pid: LocalPid, mon: Monitor) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.down_called = true;
+        if let Some(notify) = inner.notify {
+            send_from_callback(
+                notify,
+                atoms::monitor_resource_down(),
+                Some(mon) == inner.mon,
+            );
+        }
+        if inner.panic_in_down {
+            drop(inner);
+            panic!("panic in down");
+        }
+    }
+}
+
+/// Send `{tag, value}` to `pid` from a resource callback.
+///
+/// Callbacks are not called on behalf of a process, so the message is sent from a thread of our
+/// own, which also keeps panics out of the callback.
+pub fn send_from_callback(pid: LocalPid, tag: Atom, value: bool) {
+    std::thread::spawn(move || {
+        let _ = OwnedEnv::new().send_and_clear(&pid, |env| (tag, value).encode(env));
+    });
+}
+
+/// Counts its destructor calls and increments the counter passed to `dyncall`.
+pub struct CallbackResource {
+    #[cfg_attr(not(feature = "nif_version_2_16"), allow(dead_code))]
+    value: i64,
+}
+
+lazy_static::lazy_static! {
+    static ref DESTRUCTOR_CALLS: AtomicUsize = AtomicUsize::new(0);
+}
+
+impl Resource for CallbackResource {
+    #[cfg(feature = "nif_version_2_16")]
+    const IMPLEMENTS_DYNCALL: bool = true;
+
+    fn type_name() -> &'static str {
+        "callback_resource"
+    }
+
+    fn destructor(self, _env: Env<'_>) {
+        DESTRUCTOR_CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[cfg(feature = "nif_version_2_16")]
+    fn dyncall<'a>(&'a self, _env: Env<'a>, call_data: *mut std::ffi::c_void) {
+        let call_data = unsafe { &mut *(call_data as *mut i64) };
+        *call_data += self.value;
+    }
 }
 
 #[rustler::nif]
@@ -104,7 +179,91 @@ pub fn resource_make_binaries(
     )
 }
 
+#[rustler::nif]
+pub fn resource_make_iodata(env: Env, resource: ResourceArc<WithBinaries>) -> IoData {
+    let mut data = IoData::new(env);
+    data.push_binary(resource.make_binary(env, |w| &w.a))
+        .push_bytes(b"|")
+        .push_binary(resource.make_binary(env, |w| &w.b));
+    data
+}
+
 #[rustler::nif]
 pub fn resource_make_binary_from_vec(env: Env, resource: ResourceArc<WithBinaries>) -> Binary {
     resource.make_binary(env, |w| &w.b)
 }
+
+#[rustler::nif]
+pub fn monitor_resource_make() -> ResourceArc<TestMonitorResource> {
+    ResourceArc::new(TestMonitorResource {
+        inner: Mutex::new(TestMonitorResourceInner::default()),
+    })
+}
+
+#[rustler::nif]
+pub fn monitor_resource_make_panicking() -> ResourceArc<TestMonitorResource> {
+    let inner = TestMonitorResourceInner {
+        panic_in_down: true,
+        ..Default::default()
+    };
+    ResourceArc::new(TestMonitorResource {
+        inner: Mutex::new(inner),
+    })
+}
+
+#[rustler::nif]
+pub fn monitor_resource_monitor(
+    env: Env,
+    resource: ResourceArc<TestMonitorResource>,
+    pid: LocalPid,
+    notify: LocalPid,
+) -> bool {
+    let mut inner = resource.inner.lock().unwrap();
+    inner.notify = Some(notify);
+    inner.mon = resource.monitor(Some(env), &pid);
+    inner.mon.is_some()
+}
+
+#[rustler::nif]
+pub fn monitor_resource_demonitor(env: Env, resource: ResourceArc<TestMonitorResource>) -> bool {
+    let inner = resource.inner.lock().unwrap();
+    match inner.mon {
+        Some(ref mon) => resource.demonitor(Some(env), mon),
+        None => false,
+    }
+}
+
+#[rustler::nif]
+pub fn monitor_resource_down_called(resource: ResourceArc<TestMonitorResource>) -> bool {
+    resource.inner.lock().unwrap().down_called
+}
+
+#[rustler::nif]
+pub fn callback_resource_make(value: i64) -> ResourceArc<CallbackResource> {
+    ResourceArc::new(CallbackResource { value })
+}
+
+#[rustler::nif]
+pub fn callback_resource_destructor_calls() -> usize {
+    DESTRUCTOR_CALLS.load(Ordering::SeqCst)
+}
+
+#[cfg(feature = "nif_version_2_16")]
+#[rustler::nif]
+pub fn callback_resource_dyncall(env: Env, resource: Term, n: i64) -> Option<i64> {
+    let module = rustler::Atom::from_str(env, "Elixir.RustlerTest").unwrap();
+    let name = rustler::Atom::from_str(env, CallbackResource::type_name()).unwrap();
+    let mut call_data = n;
+
+    unsafe {
+        env.dynamic_resource_call(module, name, resource, &mut call_data as *mut i64 as *mut _)
+    }
+    .ok()
+    .map(|_| call_data)
+}
+
+#[cfg(not(feature = "nif_version_2_16"))]
+#[rustler::nif]
+pub fn callback_resource_dyncall(_resource: Term, _n: i64) -> Option<i64> {
+    None
+}
@@ -0,0 +1,94 @@
+use crate::test_resource::send_from_callback;
+use rustler::resource::{Event, SelectMode};
+use rustler::{Env, LocalPid, Resource, ResourceArc, Term};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+
+mod atoms {
+    rustler::atoms! {
+        select_resource_stopped,
+    }
+}
+
+/// Holds both ends of a loopback connection, so that the test can make the reading end ready by
+/// writing to the other one.
+pub struct SelectResource {
+    reader: TcpStream,
+    writer: TcpStream,
+    notify: Mutex<Option<LocalPid>>,
+}
+
+impl Resource for SelectResource {
+    const IMPLEMENTS_STOP: bool = true;
+
+    fn stop<'a>(&'a self, _env: Env<'a>, event: Event, _is_direct_call: bool) {
+        if let Some(notify) = *self.notify.lock().unwrap() {
+            let same_event = event == as_event(&self.reader);
+            send_from_callback(notify, atoms::select_resource_stopped(), same_event);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn as_event(stream: &TcpStream) -> Event {
+    use std::os::unix::io::AsRawFd;
+    stream.as_raw_fd()
+}
+
+#[cfg(windows)]
+fn as_event(stream: &TcpStream) -> Event {
+    use std::os::windows::io::AsRawSocket;
+    stream.as_raw_socket() as Event
+}
+
+#[rustler::nif]
+pub fn select_resource_make() -> ResourceArc<SelectResource> {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let writer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (reader, _) = listener.accept().unwrap();
+
+    ResourceArc::new(SelectResource {
+        reader,
+        writer,
+        notify: Mutex::new(None),
+    })
+}
+
+#[rustler::nif]
+pub fn select_resource_select_read<'a>(
+    env: Env<'a>,
+    resource: ResourceArc<SelectResource>,
+    pid: LocalPid,
+    reference: Term<'a>,
+) -> bool {
+    resource
+        .select(
+            env,
+            as_event(&resource.reader),
+            SelectMode::Read,
+            Some(&pid),
+            reference,
+        )
+        .is_ok()
+}
+
+#[rustler::nif]
+pub fn select_resource_write(resource: ResourceArc<SelectResource>) -> bool {
+    (&resource.writer).write_all(b"x").is_ok()
+}
+
+#[rustler::nif]
+pub fn select_resource_stop(env: Env, resource: ResourceArc<SelectResource>) -> bool {
+    *resource.notify.lock().unwrap() = Some(env.pid());
+    let undefined = rustler::types::atom::undefined();
+    resource
+        .select(
+            env,
+            as_event(&resource.reader),
+            SelectMode::Stop,
+            None,
+            undefined,
+        )
+        .is_ok()
+}
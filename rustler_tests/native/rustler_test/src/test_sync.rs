@@ -0,0 +1,92 @@
+use rustler::sync::{Condvar, Mutex, RwLock};
+use std::sync::Arc;
+use std::thread;
+
+/// Increment a counter behind a `Mutex` from `threads` threads, `count` times each.
+#[rustler::nif]
+pub fn sync_mutex_counter(threads: usize, count: usize) -> usize {
+    let counter = Arc::new(Mutex::new("rustler_test.counter", 0));
+
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for _ in 0..count {
+                    *counter.lock() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let total = *counter.lock();
+    total
+}
+
+#[rustler::nif]
+pub fn sync_mutex_try_lock() -> (bool, bool) {
+    let mutex = Mutex::new("rustler_test.try_lock", ());
+    let guard = mutex.lock();
+    let while_locked = mutex.try_lock().is_some();
+    drop(guard);
+    let after_unlock = mutex.try_lock().is_some();
+    (while_locked, after_unlock)
+}
+
+#[rustler::nif]
+pub fn sync_rwlock() -> (bool, bool, i64) {
+    let lock = RwLock::new("rustler_test.rwlock", 1);
+
+    let shared_read = {
+        let _first = lock.read();
+        let second = lock.try_read();
+        second.is_some()
+    };
+
+    let write_while_reading = {
+        let _reader = lock.read();
+        let writer = lock.try_write();
+        writer.is_some()
+    };
+
+    *lock.write() += 41;
+    let value = *lock.read();
+
+    (shared_read, write_while_reading, value)
+}
+
+/// Wait on a `Condvar` until another thread sets the flag.
+#[rustler::nif]
+pub fn sync_condvar() -> bool {
+    let pair = Arc::new((
+        Mutex::new("rustler_test.condvar_mutex", false),
+        Condvar::new("rustler_test.condvar"),
+    ));
+
+    let pair2 = Arc::clone(&pair);
+    let handle = thread::spawn(move || {
+        let (mutex, condvar) = &*pair2;
+        *mutex.lock() = true;
+        condvar.notify_all();
+    });
+
+    let (mutex, condvar) = &*pair;
+    let ready = *condvar.wait_while(mutex.lock(), |ready| !*ready);
+    handle.join().unwrap();
+    ready
+}
+
+#[rustler::nif]
+pub fn sync_names() -> (String, String, String) {
+    let mutex = Mutex::new("rustler_test.mutex", ());
+    let rwlock = RwLock::new("rustler_test.rwlock", ());
+    let condvar = Condvar::new("rustler_test.cond");
+    (
+        mutex.name().to_owned(),
+        rwlock.name().to_owned(),
+        condvar.name().to_owned(),
+    )
+}
@@ -0,0 +1,19 @@
+use rustler::system;
+
+#[rustler::nif]
+pub fn system_info_fields() -> (String, String, (u32, u32), usize, bool, bool) {
+    let info = rustler::system_info();
+    (
+        info.otp_release,
+        info.erts_version,
+        info.nif_version,
+        info.scheduler_threads,
+        info.dirty_scheduler_support,
+        info.smp_support,
+    )
+}
+
+#[rustler::nif]
+pub fn system_getenv(key: &str) -> Option<String> {
+    system::getenv(key)
+}
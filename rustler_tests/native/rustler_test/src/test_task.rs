@@ -0,0 +1,90 @@
+use rustler::task::{self, Task, ThreadExecutor};
+use rustler::thread::PoolConfig;
+use rustler::{Env, PoolSpawner, Reference};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+/// A future that completes after a delay, waking its task from another thread.
+struct Delay {
+    done: Arc<AtomicBool>,
+    duration: Duration,
+    started: bool,
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.done.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        if !self.started {
+            self.started = true;
+            let done = Arc::clone(&self.done);
+            let waker = cx.waker().clone();
+            let duration = self.duration;
+            thread::spawn(move || {
+                thread::sleep(duration);
+                done.store(true, Ordering::Release);
+                waker.wake();
+            });
+        }
+        Poll::Pending
+    }
+}
+
+fn delay(msec: u64) -> Delay {
+    Delay {
+        done: Arc::new(AtomicBool::new(false)),
+        duration: Duration::from_millis(msec),
+        started: false,
+    }
+}
+
+#[rustler::nif]
+pub async fn task_sleep(msec: u64) -> u64 {
+    delay(msec).await;
+    msec
+}
+
+#[rustler::nif]
+pub async fn task_panic() -> u64 {
+    delay(10).await;
+    panic!("task_panic: failed");
+}
+
+#[rustler::nif]
+pub fn task_pooled_add(
+    a: i64,
+    b: i64,
+) -> Task<impl Future<Output = i64>, ThreadExecutor<PoolSpawner>> {
+    Task::with_executor(async move { a + b })
+}
+
+struct SmallTaskPool;
+
+impl PoolConfig for SmallTaskPool {
+    const NAME: &'static str = "rustler_test_task_pool";
+    const WORKERS: usize = 1;
+    const QUEUE_SIZE: usize = 1;
+}
+
+#[rustler::nif]
+pub fn task_pooled_sleep(
+    msec: u64,
+) -> Task<impl Future<Output = u64>, ThreadExecutor<PoolSpawner<SmallTaskPool>>> {
+    Task::with_executor(async move {
+        delay(msec).await;
+        msec
+    })
+}
+
+#[rustler::nif]
+pub fn task_spawn(env: Env, values: Vec<i64>) -> Reference {
+    task::spawn::<ThreadExecutor, _>(env, async move { values.iter().sum::<i64>() })
+}
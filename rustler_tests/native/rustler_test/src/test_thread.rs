@@ -3,6 +3,8 @@ use rustler::types::atom;
 use rustler::{Atom, Encoder, Env};
 
 use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[rustler::nif]
 pub fn threaded_fac(env: Env, n: u64) -> Atom {
@@ -35,3 +37,114 @@ pub fn threaded_sleep(env: Env, msec: u64) -> Atom {
 
     atom::ok()
 }
+
+struct NamedThreads;
+
+impl thread::ThreadConfig for NamedThreads {
+    const NAME: &'static str = "rustler_test_spawner";
+    const STACK_SIZE: Option<usize> = Some(256 * 1024);
+}
+
+#[rustler::nif]
+pub fn erlang_threaded_fac(env: Env, n: u64) -> Atom {
+    thread::spawn::<thread::ErlangThreadSpawner<NamedThreads>, _>(env, move |thread_env| {
+        let name = thread::current_name();
+        let result: u64 = (1..=n).product();
+        (name, result).encode(thread_env)
+    });
+
+    atom::ok()
+}
+
+/// Start a thread with `thread::Builder` and join it, returning the thread's name as seen from
+/// the handle and from the thread itself, and the thread's result.
+#[rustler::nif]
+pub fn thread_builder_join(name: String, fail: bool) -> (Option<String>, Option<String>, String) {
+    panic::set_hook(Box::new(|_info| {}));
+
+    let handle = thread::Builder::new(&name)
+        .stack_size(128 * 1024)
+        .spawn(move || {
+            if fail {
+                panic!("thread_builder_join: failed");
+            }
+            thread::current_name()
+        })
+        .unwrap();
+
+    let handle_name = handle.name().map(str::to_owned);
+    match handle.join() {
+        Ok(thread_name) => (handle_name, thread_name, "ok".to_owned()),
+        Err(_) => (handle_name, None, "panicked".to_owned()),
+    }
+}
+
+struct SmallPool;
+
+impl thread::PoolConfig for SmallPool {
+    const NAME: &'static str = "rustler_test_pool";
+    const WORKERS: usize = 1;
+    const QUEUE_SIZE: usize = 1;
+}
+
+#[rustler::nif]
+pub fn pooled_sleep(env: Env, msec: u64) -> Atom {
+    thread::spawn::<thread::PoolSpawner<SmallPool>, _>(env, move |thread_env| {
+        std::thread::sleep(std::time::Duration::from_millis(msec));
+        msec.encode(thread_env)
+    });
+
+    atom::ok()
+}
+
+#[rustler::nif]
+pub fn pooled_fac(env: Env, n: u64) -> Atom {
+    thread::spawn::<thread::PoolSpawner, _>(env, move |thread_env| {
+        let result: u64 = (1..=n).product();
+        result.encode(thread_env)
+    });
+
+    atom::ok()
+}
+
+struct CountDrops(usize, Arc<AtomicUsize>);
+
+impl Drop for CountDrops {
+    fn drop(&mut self) {
+        self.1.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Give each of `threads` threads its own value for a `TsdKey`, and return the values seen by the
+/// threads and how many values were dropped with the key. Threads with an even index clear their
+/// value before they exit.
+#[rustler::nif]
+pub fn tsd_key_values(threads: usize) -> (Vec<usize>, usize) {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let key = Arc::new(thread::TsdKey::new("rustler_test.tsd"));
+
+    let handles: Vec<_> = (0..threads)
+        .map(|index| {
+            let key = Arc::clone(&key);
+            let drops = Arc::clone(&drops);
+            std::thread::spawn(move || {
+                assert!(key.get().is_none());
+                let value = key.get_or_init(|| CountDrops(index, drops.clone())).0;
+                assert!(key.set(CountDrops(usize::MAX, drops)).is_err());
+                if index % 2 == 0 {
+                    let taken = unsafe { key.take() }.unwrap();
+                    assert_eq!(taken.0, value);
+                    assert!(key.get().is_none());
+                }
+                value
+            })
+        })
+        .collect();
+
+    let values = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    // The values rejected by `set` and the values taken were dropped already.
+    let rejected = drops.load(Ordering::SeqCst);
+
+    drop(Arc::try_unwrap(key).ok().unwrap());
+    (values, drops.load(Ordering::SeqCst) - rejected)
+}
@@ -0,0 +1,48 @@
+use rustler::time::{self, Instant, TimeUnit};
+use rustler::{Env, NifResult, Term};
+
+#[rustler::nif]
+pub fn time_monotonic(unit: TimeUnit) -> i64 {
+    time::monotonic_time(unit)
+}
+
+#[rustler::nif]
+pub fn time_system(unit: TimeUnit) -> i64 {
+    time::system_time(unit)
+}
+
+#[rustler::nif]
+pub fn time_offset(unit: TimeUnit) -> i64 {
+    time::time_offset(unit)
+}
+
+#[rustler::nif]
+pub fn time_convert(time: i64, from: TimeUnit, to: TimeUnit) -> i64 {
+    time::convert_time_unit(time, from, to)
+}
+
+#[rustler::nif]
+pub fn time_unit_echo(unit: TimeUnit) -> TimeUnit {
+    unit
+}
+
+#[rustler::nif]
+pub fn time_now(env: Env) -> Term {
+    time::now_time(env)
+}
+
+#[rustler::nif]
+pub fn time_cpu(env: Env) -> NifResult<Term> {
+    time::cpu_time(env)
+}
+
+/// Return the microseconds elapsed since the given monotonic time, and that time after a round
+/// trip through `Instant`.
+#[rustler::nif]
+pub fn time_instant_elapsed(started_at: i64, unit: TimeUnit) -> (u64, i64) {
+    let instant = Instant::from_monotonic_time(started_at, unit);
+    (
+        instant.elapsed().as_micros() as u64,
+        instant.as_monotonic_time(unit),
+    )
+}
@@ -0,0 +1,41 @@
+use rustler::{Env, OldPrivData, Term};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static UPGRADE_CALLS: AtomicUsize = AtomicUsize::new(0);
+static UNLOAD_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// The private data of the library, carried over to the new library on every upgrade.
+pub struct LibraryState {
+    generation: usize,
+}
+
+pub fn load(_env: Env, _load_info: Term) -> Option<LibraryState> {
+    Some(LibraryState { generation: 0 })
+}
+
+pub fn upgrade(_env: Env, old_state: OldPrivData, _load_info: Term) -> Option<LibraryState> {
+    UPGRADE_CALLS.fetch_add(1, Ordering::SeqCst);
+    Some(LibraryState {
+        generation: old_state.downcast_ref::<LibraryState>()?.generation + 1,
+    })
+}
+
+pub fn unload(_env: Env) {
+    UNLOAD_CALLS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[rustler::nif]
+pub fn upgrade_calls() -> usize {
+    UPGRADE_CALLS.load(Ordering::SeqCst)
+}
+
+#[rustler::nif]
+pub fn unload_calls() -> usize {
+    UNLOAD_CALLS.load(Ordering::SeqCst)
+}
+
+#[rustler::nif]
+pub fn library_generation(env: Env) -> Option<usize> {
+    env.priv_data::<LibraryState>()
+        .map(|state| state.generation)
+}
@@ -0,0 +1,102 @@
+use rustler::schedule::{consume_timeslice, current_scheduler, Done, Reschedule, Yield, Yielding};
+use rustler::schedule::{fold_list, fold_map, ListFold, MapFold};
+use rustler::Env;
+use rustler::{NifResult, SchedulerFlags, Term};
+
+/// Sum the numbers below `n`, yielding whenever the timeslice is used up. Returns the sum and the
+/// number of times the NIF yielded.
+#[rustler::nif(yielding)]
+pub fn yielding_sum(
+    env: Env,
+    n: u64,
+    state: Option<(u64, u64, u64)>,
+) -> Yielding<(u64, u64, u64), (u64, u64)> {
+    let (mut i, mut acc, yields) = state.unwrap_or((0, 0, 0));
+
+    while i < n {
+        acc += i;
+        i += 1;
+
+        if i % 1000 == 0 && consume_timeslice(env, 1) {
+            return Yield((i, acc, yields + 1));
+        }
+    }
+
+    Done((acc, yields))
+}
+
+/// Yield `count` times unconditionally before returning the list of steps.
+#[rustler::nif(yielding, name = "yielding_steps")]
+pub fn yielding_steps_impl(
+    count: usize,
+    steps: Option<Vec<usize>>,
+) -> Yielding<Vec<usize>, Vec<usize>> {
+    let mut steps = steps.unwrap_or_default();
+    if steps.len() == count {
+        Done(steps)
+    } else {
+        steps.push(steps.len());
+        Yield(steps)
+    }
+}
+
+fn scheduler_name(scheduler: Option<SchedulerFlags>) -> &'static str {
+    match scheduler {
+        Some(SchedulerFlags::Normal) => "normal",
+        Some(SchedulerFlags::DirtyCpu) => "dirty_cpu",
+        Some(SchedulerFlags::DirtyIo) => "dirty_io",
+        None => "none",
+    }
+}
+
+/// Move to the scheduler named `to` if given and back to a normal scheduler, and return the names
+/// of the schedulers the NIF ran on.
+#[rustler::nif(yielding)]
+pub fn yielding_switch_scheduler(
+    to: Option<String>,
+    state: Option<Vec<&'static str>>,
+) -> Yielding<Vec<&'static str>, Vec<&'static str>> {
+    let mut seen = state.unwrap_or_default();
+    seen.push(scheduler_name(current_scheduler()));
+
+    let target = match to.as_deref() {
+        Some("dirty_cpu") => SchedulerFlags::DirtyCpu,
+        Some("dirty_io") => SchedulerFlags::DirtyIo,
+        _ => SchedulerFlags::Normal,
+    };
+
+    match seen.len() {
+        // Switch to the target scheduler, then yield once to check we stay there.
+        1 if to.is_some() => Reschedule(target, seen),
+        2 => Yield(seen),
+        3 => Reschedule(SchedulerFlags::Normal, seen),
+        _ => Done(seen),
+    }
+}
+
+#[rustler::nif]
+pub fn fold_sum_list(list: Term) -> ListFold<i64, impl FnMut(i64, Term) -> NifResult<i64>> {
+    fold_list(list, 0, |acc, term| Ok(acc + term.decode::<i64>()?))
+}
+
+/// Decode a list of integers into a `Vec` and return its length and maximum.
+#[allow(clippy::type_complexity)]
+#[rustler::nif]
+pub fn fold_decode_list(
+    list: Term,
+) -> ListFold<
+    Vec<i64>,
+    impl FnMut(Vec<i64>, Term) -> NifResult<Vec<i64>>,
+    impl FnOnce(Vec<i64>) -> (usize, Option<i64>),
+> {
+    fold_list(list, Vec::new(), |mut acc, term| {
+        acc.push(term.decode::<i64>()?);
+        Ok(acc)
+    })
+    .finish(|values| (values.len(), values.iter().max().copied()))
+}
+
+#[rustler::nif]
+pub fn fold_sum_map(map: Term) -> MapFold<i64, impl FnMut(i64, Term, Term) -> NifResult<i64>> {
+    fold_map(map, 0, |acc, _key, value| Ok(acc + value.decode::<i64>()?))
+}
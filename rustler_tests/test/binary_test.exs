@@ -48,4 +48,107 @@ defmodule RustlerTest.BinaryTest do
   test "decode iolist as binary" do
     assert RustlerTest.decode_iolist(["hi", " ", "there"]) == ["hi", " ", "there"]
   end
+
+  test "inspect a list of binaries as iovec" do
+    assert {8, [2, 1, 5], "hi there"} == RustlerTest.iovec_inspect(["hi", " ", "there"])
+    assert {0, [], ""} == RustlerTest.iovec_inspect([])
+
+    large = :binary.copy("a", 100_000)
+    assert {100_000, _, ^large} = RustlerTest.iovec_inspect(large)
+  end
+
+  test "iovec rejects nested iolists and other terms" do
+    assert_raise ArgumentError, fn -> RustlerTest.iovec_inspect(["hi", [" ", "there"]]) end
+    assert_raise ArgumentError, fn -> RustlerTest.iovec_inspect([1, 2, 3]) end
+    assert_raise ArgumentError, fn -> RustlerTest.iovec_inspect(:atom) end
+  end
+
+  test "inspect a long list of binaries in parts" do
+    list = Enum.map(1..10, &Integer.to_string/1)
+
+    {first, tail} = RustlerTest.iovec_inspect_with_max(list, 4)
+    assert first == "1234"
+    assert tail == Enum.drop(list, 4)
+
+    assert {"5678910", nil} == RustlerTest.iovec_inspect_with_max(tail, 100)
+  end
+
+  test "build iodata from binaries and bytes" do
+    iodata = RustlerTest.iodata_build("hello")
+
+    assert [<<0, 5, ?:>>, "hello", "el", "end"] == iodata
+    assert <<0, 5, ":helloelend">> == IO.iodata_to_binary(iodata)
+  end
+
+  test "iodata references large binaries" do
+    <<_::binary-size(10), large::binary-size(100_000), _::binary>> = :binary.copy("a", 100_020)
+
+    assert {200_003, [first, "abc", last]} = RustlerTest.iodata_len([large, "abc", large])
+    assert large == first
+    assert large == last
+    # Copies would only hold the pushed part of the original binary.
+    assert 100_020 == :binary.referenced_byte_size(first)
+    assert 100_020 == :binary.referenced_byte_size(last)
+
+    assert {0, []} == RustlerTest.iodata_len([])
+  end
+
+  test "decode bitstrings" do
+    assert {12, 4, false} == RustlerTest.bitstring_info(<<1::3, 5::5, 7::4>>)
+    assert {16, 0, true} == RustlerTest.bitstring_info("ab")
+    assert {3, 3, false} == RustlerTest.bitstring_info(<<5::3>>)
+    assert {0, 0, true} == RustlerTest.bitstring_info(<<>>)
+
+    assert_raise ArgumentError, fn -> RustlerTest.bitstring_info(:atom) end
+    assert_raise ArgumentError, fn -> RustlerTest.bitstring_info([1, 2]) end
+  end
+
+  test "encode bitstrings" do
+    bits = <<1::3, 5::5, 7::4>>
+    assert bits == RustlerTest.bitstring_echo(bits)
+    assert "ab" == RustlerTest.bitstring_echo("ab")
+
+    assert <<1::3>> == RustlerTest.bitstring_new(<<0x3F>>, 3)
+    assert <<0x25, 7::4>> == RustlerTest.bitstring_new(<<0x25, 0x7F>>, 12)
+    assert "ab" == RustlerTest.bitstring_new("abc", 16)
+    assert_raise ErlangError, fn -> RustlerTest.bitstring_new("a", 9) end
+  end
+
+  test "sub bitstrings" do
+    bits = <<1::3, 5::5, 7::4>>
+
+    assert <<1::3>> == RustlerTest.bitstring_sub(bits, 0, 3)
+    assert <<5::5>> == RustlerTest.bitstring_sub(bits, 3, 5)
+    assert <<5::5, 7::4>> == RustlerTest.bitstring_sub(bits, 3, 9)
+    assert <<0x25>> == RustlerTest.bitstring_sub(bits, 0, 8)
+    assert <<>> == RustlerTest.bitstring_sub(bits, 12, 0)
+    assert "bc" == RustlerTest.bitstring_sub("abcd", 8, 16)
+
+    assert_raise ArgumentError, fn -> RustlerTest.bitstring_sub(bits, 8, 5) end
+  end
+
+  test "write into a growing binary" do
+    assert {0, ""} == RustlerTest.binary_writer_join(0)
+    assert {capacity, "0,1,2"} = RustlerTest.binary_writer_join(3)
+    assert capacity >= 5
+
+    {capacity, joined} = RustlerTest.binary_writer_join(10_000)
+    assert joined == Enum.join(0..9_999, ",")
+    assert capacity >= byte_size(joined)
+  end
+
+  test "read lines from a binary" do
+    assert ["first", "second", "", "last"] ==
+             RustlerTest.binary_reader_lines("first\nsecond\n\nlast")
+
+    assert [] == RustlerTest.binary_reader_lines("")
+  end
+
+  test "read sub-binaries from a binary" do
+    assert {"hello", " world"} == RustlerTest.binary_reader_frame(<<5::16, "hello world">>)
+    assert {"", ""} == RustlerTest.binary_reader_frame(<<0::16>>)
+
+    assert_raise ArgumentError, fn -> RustlerTest.binary_reader_frame(<<10::16, "short">>) end
+    assert_raise ArgumentError, fn -> RustlerTest.binary_reader_frame(<<1>>) end
+  end
 end
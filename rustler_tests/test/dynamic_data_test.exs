@@ -7,4 +7,8 @@ defmodule RustlerTest.DynamicDataTest do
 
     assert File.read!(path) == DynamicData.get_dataset()
   end
+
+  test "process-independent environments have no private data" do
+    refute DynamicData.owned_env_has_dataset()
+  end
 end
@@ -86,6 +86,40 @@ defmodule RustlerTest.EnvTest do
     assert false == RustlerTest.is_process_alive(task.pid)
   end
 
+  test "whereis_port" do
+    {:ok, port} = :gen_tcp.listen(0, [])
+    assert port == RustlerTest.whereis_port(port)
+
+    Process.register(port, :rustler_test_port)
+    assert port == RustlerTest.whereis_port(:rustler_test_port)
+
+    assert nil == RustlerTest.whereis_port(self())
+    assert nil == RustlerTest.whereis_port(:not_a_registered_name)
+    :gen_tcp.close(port)
+  end
+
+  test "is_port_alive" do
+    {:ok, port} = :gen_tcp.listen(0, [])
+    assert true == RustlerTest.is_port_alive(port)
+
+    :gen_tcp.close(port)
+    assert false == RustlerTest.is_port_alive(port)
+
+    assert_raise ArgumentError, fn -> RustlerTest.is_port_alive(self()) end
+  end
+
+  unless match?({:win32, _}, :os.type()) do
+    test "port_command" do
+      port = Port.open({:spawn, "cat"}, [:binary])
+
+      assert :ok == RustlerTest.port_command(port, ["hello", ?\s, "port"])
+      assert_receive {^port, {:data, "hello port"}}
+
+      Port.close(port)
+      assert :error == RustlerTest.port_command(port, "closed")
+    end
+  end
+
   test "send_error" do
     task =
       Task.async(fn ->
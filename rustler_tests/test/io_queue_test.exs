@@ -0,0 +1,55 @@
+defmodule RustlerTest.IoQueueTest do
+  use ExUnit.Case, async: true
+
+  test "push binaries and lists of binaries" do
+    queue = RustlerTest.io_queue_new()
+
+    assert 5 == RustlerTest.io_queue_push(queue, "hello")
+    assert 11 == RustlerTest.io_queue_push(queue, [" ", "world"])
+    assert 11 == RustlerTest.io_queue_push(queue, [])
+    assert 16 == RustlerTest.io_queue_push_owned(queue, "!!!!!")
+
+    assert "hello world!!!!!" == IO.iodata_to_binary(RustlerTest.io_queue_chunks(queue))
+  end
+
+  test "push rejects other terms" do
+    queue = RustlerTest.io_queue_new()
+
+    assert_raise ArgumentError, fn -> RustlerTest.io_queue_push(queue, :atom) end
+    assert_raise ArgumentError, fn -> RustlerTest.io_queue_push(queue, [1, 2, 3]) end
+  end
+
+  test "large binaries are pushed without copying" do
+    queue = RustlerTest.io_queue_new()
+    <<_::binary-size(10), large::binary-size(100_000), _::binary>> = :binary.copy("a", 100_020)
+
+    RustlerTest.io_queue_push(queue, large)
+    head = RustlerTest.io_queue_peek_head(queue)
+    assert large == head
+    # A copy would only hold the pushed part of the original binary.
+    assert 100_020 == :binary.referenced_byte_size(head)
+  end
+
+  test "peek and dequeue" do
+    queue = RustlerTest.io_queue_new()
+    assert nil == RustlerTest.io_queue_peek_head(queue)
+
+    RustlerTest.io_queue_push(queue, :erlang.iolist_to_iovec(["abc", "def"]))
+    assert RustlerTest.io_queue_dequeue(queue, 2)
+    assert "cdef" == IO.iodata_to_binary(RustlerTest.io_queue_chunks(queue))
+    refute RustlerTest.io_queue_dequeue(queue, 5)
+    assert RustlerTest.io_queue_dequeue(queue, 4)
+    assert [] == RustlerTest.io_queue_chunks(queue)
+  end
+
+  test "read from the queue" do
+    queue = RustlerTest.io_queue_new()
+    RustlerTest.io_queue_push(queue, ["first line\nsec", "ond line\n", "rest"])
+
+    assert "first line\n" == RustlerTest.io_queue_read_line(queue)
+    assert "second line\n" == RustlerTest.io_queue_read_line(queue)
+    assert "re" == RustlerTest.io_queue_read(queue, 2)
+    assert "st" == RustlerTest.io_queue_read(queue, 10)
+    assert "" == RustlerTest.io_queue_read(queue, 10)
+  end
+end
@@ -0,0 +1,28 @@
+defmodule RustlerTest.ReferenceTest do
+  use ExUnit.Case, async: true
+
+  test "make_ref" do
+    ref = RustlerTest.make_ref()
+    assert is_reference(ref)
+    assert ref != RustlerTest.make_ref()
+  end
+
+  test "reference encoding and decoding" do
+    ref = make_ref()
+    assert RustlerTest.reference_echo(ref) == ref
+    assert_raise ArgumentError, fn -> RustlerTest.reference_echo(:not_a_ref) end
+  end
+
+  test "owned reference reply from thread" do
+    ref = make_ref()
+    assert :ok == RustlerTest.reference_reply_from_thread(self(), ref)
+    assert_receive {^ref, :ok}
+  end
+
+  test "owned reference stored in a resource" do
+    ref = make_ref()
+    request = RustlerTest.reference_store(ref)
+    assert RustlerTest.reference_take(request) == ref
+    assert RustlerTest.reference_take(request) == nil
+  end
+end
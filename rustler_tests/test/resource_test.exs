@@ -44,4 +44,84 @@ defmodule RustlerTest.ResourceTest do
     assert slice == vec
     assert vec == static
   end
+
+  test "resource binaries in iodata" do
+    res = RustlerTest.resource_make_with_binaries()
+    bytes = Enum.into(0..9, <<>>, &<<&1>>)
+
+    assert [bytes, "|", bytes] == RustlerTest.resource_make_iodata(res)
+  end
+
+  test "monitor resource" do
+    resource = RustlerTest.monitor_resource_make()
+    parent = self()
+
+    spawn(fn ->
+      assert RustlerTest.monitor_resource_monitor(resource, self(), parent)
+      send(parent, :monitored)
+    end)
+
+    assert_receive :monitored
+    assert_receive {:monitor_resource_down, true}
+    assert RustlerTest.monitor_resource_down_called(resource)
+  end
+
+  test "panic in monitor resource callback" do
+    resource = RustlerTest.monitor_resource_make_panicking()
+
+    pid =
+      spawn(fn ->
+        receive do
+          :exit -> :ok
+        end
+      end)
+
+    assert RustlerTest.monitor_resource_monitor(resource, pid, self())
+    send(pid, :exit)
+
+    assert_receive {:monitor_resource_down, true}
+    assert RustlerTest.monitor_resource_down_called(resource)
+  end
+
+  test "monitor resource demonitor" do
+    resource = RustlerTest.monitor_resource_make()
+
+    pid =
+      spawn(fn ->
+        receive do
+          :exit -> :ok
+        end
+      end)
+
+    assert RustlerTest.monitor_resource_monitor(resource, pid, self())
+    assert RustlerTest.monitor_resource_demonitor(resource)
+    send(pid, :exit)
+    refute_receive {:monitor_resource_down, _}, 50
+    refute RustlerTest.monitor_resource_down_called(resource)
+    refute RustlerTest.monitor_resource_demonitor(resource)
+  end
+
+  test "resource destructor callback" do
+    before = RustlerTest.callback_resource_destructor_calls()
+
+    for i <- 0..100 do
+      RustlerTest.callback_resource_make(i)
+    end
+
+    :erlang.garbage_collect()
+    :timer.sleep(100)
+
+    assert RustlerTest.callback_resource_destructor_calls() - before >= 101
+  end
+
+  if RustlerTest.Helper.nif_feature_from_running_version() in [
+       "nif_version_2_16",
+       "nif_version_2_17"
+     ] do
+    test "resource dyncall callback" do
+      resource = RustlerTest.callback_resource_make(40)
+      assert RustlerTest.callback_resource_dyncall(resource, 2) == 42
+      assert RustlerTest.callback_resource_dyncall(make_ref(), 2) == nil
+    end
+  end
 end
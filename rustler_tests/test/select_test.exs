@@ -0,0 +1,28 @@
+defmodule RustlerTest.SelectTest do
+  use ExUnit.Case, async: true
+
+  # enif_select is not supported on Windows
+  unless match?({:win32, _}, :os.type()) do
+    test "select read readiness" do
+      resource = RustlerTest.select_resource_make()
+      ref = make_ref()
+
+      assert RustlerTest.select_resource_select_read(resource, self(), ref)
+      refute_receive {:select, _, _, _}, 50
+
+      assert RustlerTest.select_resource_write(resource)
+      assert_receive {:select, ^resource, ^ref, :ready_input}
+    end
+
+    test "select stop calls the stop callback" do
+      resource = RustlerTest.select_resource_make()
+      ref = make_ref()
+
+      assert RustlerTest.select_resource_select_read(resource, self(), ref)
+      assert RustlerTest.select_resource_stop(resource)
+
+      assert_receive {:select_resource_stopped, true}
+      refute_receive {:select, _, _, _}, 50
+    end
+  end
+end
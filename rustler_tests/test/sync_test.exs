@@ -0,0 +1,24 @@
+defmodule RustlerTest.SyncTest do
+  use ExUnit.Case, async: true
+
+  test "mutex protects a counter across threads" do
+    assert 4000 == RustlerTest.sync_mutex_counter(4, 1000)
+  end
+
+  test "mutex try_lock" do
+    assert {false, true} == RustlerTest.sync_mutex_try_lock()
+  end
+
+  test "rwlock allows shared readers and exclusive writers" do
+    assert {true, false, 42} == RustlerTest.sync_rwlock()
+  end
+
+  test "condvar wakes up a waiting thread" do
+    assert RustlerTest.sync_condvar()
+  end
+
+  test "locks are named" do
+    assert {"rustler_test.mutex", "rustler_test.rwlock", "rustler_test.cond"} ==
+             RustlerTest.sync_names()
+  end
+end
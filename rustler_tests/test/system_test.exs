@@ -0,0 +1,28 @@
+defmodule RustlerTest.SystemTest do
+  use ExUnit.Case, async: true
+
+  test "system info matches erlang:system_info/1" do
+    {otp_release, erts_version, {nif_major, nif_minor}, schedulers, dirty, smp} =
+      RustlerTest.system_info_fields()
+
+    assert otp_release == List.to_string(:erlang.system_info(:otp_release))
+    assert erts_version == List.to_string(:erlang.system_info(:version))
+    assert nif_major == 2
+    assert nif_minor >= 14
+    assert schedulers == :erlang.system_info(:schedulers)
+    assert dirty == :erlang.system_info(:dirty_cpu_schedulers) > 0
+    assert smp == :erlang.system_info(:smp_support)
+  end
+
+  test "getenv reads the environment of the VM" do
+    System.put_env("RUSTLER_TEST_GETENV", "value")
+    assert "value" == RustlerTest.system_getenv("RUSTLER_TEST_GETENV")
+
+    long = String.duplicate("x", 1000)
+    System.put_env("RUSTLER_TEST_GETENV", long)
+    assert long == RustlerTest.system_getenv("RUSTLER_TEST_GETENV")
+
+    System.delete_env("RUSTLER_TEST_GETENV")
+    assert nil == RustlerTest.system_getenv("RUSTLER_TEST_GETENV")
+  end
+end
@@ -0,0 +1,50 @@
+defmodule RustlerTest.TaskTest do
+  use ExUnit.Case, async: true
+
+  test "async nif replies with the reference" do
+    ref = RustlerTest.task_sleep(50)
+    assert is_reference(ref)
+    assert_receive {^ref, 50}, 1000
+  end
+
+  test "async nif panic" do
+    ref = RustlerTest.task_panic()
+    assert_receive {^ref, {:error, "task_panic: failed"}}, 1000
+  end
+
+  test "task on a pooled executor" do
+    ref = RustlerTest.task_pooled_add(1, 2)
+    assert_receive {^ref, 3}, 1000
+  end
+
+  test "pooled executor rejects tasks when the queue is full" do
+    # The pool has a single worker and room for one waiting task.
+    refs = Enum.map(1..5, fn _ -> RustlerTest.task_pooled_sleep(100) end)
+
+    results =
+      Enum.map(refs, fn ref ->
+        receive do
+          {^ref, result} -> result
+        after
+          1000 -> :timeout
+        end
+      end)
+
+    assert Enum.count(results, &(&1 == {:error, :queue_full})) >= 3
+    assert Enum.count(results, &(&1 == 100)) >= 1
+    refute :timeout in results
+  end
+
+  test "task spawn" do
+    ref = RustlerTest.task_spawn([1, 2, 3])
+    assert_receive {^ref, 6}, 1000
+  end
+
+  test "concurrent tasks reply to their own reference" do
+    refs = Enum.map([100, 10, 50], fn msec -> {RustlerTest.task_sleep(msec), msec} end)
+
+    for {ref, msec} <- refs do
+      assert_receive {^ref, ^msec}, 1000
+    end
+  end
+end
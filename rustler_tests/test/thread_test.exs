@@ -59,4 +59,57 @@ defmodule RustlerTest.ThreadTest do
       msg -> assert msg == {:error, "threaded_fac: integer overflow"}
     end
   end
+
+  test "threaded nif on an erlang thread" do
+    RustlerTest.erlang_threaded_fac(10)
+
+    receive do
+      x -> assert x == {"rustler_test_spawner", 3_628_800}
+    end
+  end
+
+  test "thread builder join" do
+    assert {"worker", "worker", "ok"} == RustlerTest.thread_builder_join("worker", false)
+  end
+
+  test "thread builder join after panic" do
+    assert {"worker", nil, "panicked"} == RustlerTest.thread_builder_join("worker", true)
+  end
+
+  test "pooled jobs" do
+    Enum.each(1..20, &RustlerTest.pooled_fac/1)
+
+    results =
+      Enum.map(1..20, fn _ ->
+        receive do
+          x -> x
+        after
+          1000 -> :timeout
+        end
+      end)
+
+    assert Enum.sort(results) == Enum.map(1..20, fn n -> Enum.reduce(1..n, &*/2) end)
+  end
+
+  test "pool rejects jobs when the queue is full" do
+    # The pool has a single worker and room for one waiting job.
+    Enum.each(1..5, fn _ -> RustlerTest.pooled_sleep(100) end)
+
+    results =
+      Enum.map(1..5, fn _ ->
+        receive do
+          x -> x
+        after
+          1000 -> :timeout
+        end
+      end)
+
+    assert Enum.count(results, &(&1 == {:error, :queue_full})) >= 3
+    assert Enum.count(results, &(&1 == 100)) >= 1
+    refute :timeout in results
+  end
+
+  test "thread specific data" do
+    assert {[0, 1, 2, 3], 2} == RustlerTest.tsd_key_values(4)
+  end
 end
@@ -0,0 +1,58 @@
+defmodule RustlerTest.TimeTest do
+  use ExUnit.Case, async: true
+
+  test "monotonic time matches erlang:monotonic_time/1" do
+    for unit <- [:second, :millisecond, :microsecond, :nanosecond] do
+      before = :erlang.monotonic_time(unit)
+      time = RustlerTest.time_monotonic(unit)
+      assert before <= time
+      assert time <= :erlang.monotonic_time(unit)
+    end
+  end
+
+  test "system time matches erlang:system_time/1" do
+    for unit <- [:second, :millisecond, :microsecond, :nanosecond] do
+      before = :erlang.system_time(unit)
+      time = RustlerTest.time_system(unit)
+      assert before <= time
+      assert time <= :erlang.system_time(unit)
+    end
+  end
+
+  test "time offset matches erlang:time_offset/1" do
+    assert_in_delta :erlang.time_offset(:millisecond), RustlerTest.time_offset(:millisecond), 1
+  end
+
+  test "convert time units" do
+    assert 1_500_000 == RustlerTest.time_convert(1500, :millisecond, :microsecond)
+    assert 1 == RustlerTest.time_convert(1999, :millisecond, :second)
+
+    assert :erlang.convert_time_unit(-1500, :millisecond, :second) ==
+             RustlerTest.time_convert(-1500, :millisecond, :second)
+  end
+
+  test "time units encode and decode as atoms" do
+    for unit <- [:second, :millisecond, :microsecond, :nanosecond] do
+      assert unit == RustlerTest.time_unit_echo(unit)
+    end
+
+    assert_raise ArgumentError, fn -> RustlerTest.time_unit_echo(:native) end
+    assert_raise ArgumentError, fn -> RustlerTest.time_unit_echo("second") end
+  end
+
+  test "now and cpu time are timestamps" do
+    assert {mega, sec, micro} = RustlerTest.time_now()
+    assert is_integer(mega) and is_integer(sec) and is_integer(micro)
+
+    assert {mega, sec, micro} = RustlerTest.time_cpu()
+    assert is_integer(mega) and is_integer(sec) and is_integer(micro)
+  end
+
+  test "instants line up with erlang:monotonic_time/1" do
+    started_at = :erlang.monotonic_time(:microsecond)
+    Process.sleep(10)
+
+    assert {elapsed, ^started_at} = RustlerTest.time_instant_elapsed(started_at, :microsecond)
+    assert elapsed >= 10_000
+  end
+end
@@ -0,0 +1,47 @@
+defmodule RustlerTest.UpgradeTest do
+  # Reloads `RustlerTest`, so this must not run concurrently with other tests.
+  use ExUnit.Case, async: false
+
+  test "reloading the module upgrades the NIF library and takes over resources" do
+    resource = RustlerTest.resource_make()
+    RustlerTest.resource_set_integer_field(resource, 42)
+    upgrade_calls = RustlerTest.upgrade_calls()
+    unload_calls = RustlerTest.unload_calls()
+    generation = RustlerTest.library_generation()
+
+    {RustlerTest, binary, path} = :code.get_object_code(RustlerTest)
+    assert {:module, RustlerTest} == :code.load_binary(RustlerTest, path, binary)
+    assert RustlerTest.upgrade_calls() == upgrade_calls + 1
+
+    # The private data of the old library is passed to `upgrade`.
+    assert RustlerTest.library_generation() == generation + 1
+
+    # The resource type now belongs to the new instance of the library.
+    assert RustlerTest.resource_get_integer_field(resource) == 42
+    new_resource = RustlerTest.resource_make()
+    assert RustlerTest.resource_get_integer_field(new_resource) == 0
+
+    :code.purge(RustlerTest)
+    assert RustlerTest.unload_calls() == unload_calls + 1
+  end
+
+  test "unloading the old library keeps the threads and pools of the new one" do
+    # Start the pool of the old library.
+    RustlerTest.pooled_fac(3)
+    assert_receive 6
+
+    {RustlerTest, binary, path} = :code.get_object_code(RustlerTest)
+    assert {:module, RustlerTest} == :code.load_binary(RustlerTest, path, binary)
+
+    RustlerTest.pooled_fac(4)
+    assert_receive 24
+
+    :code.purge(RustlerTest)
+
+    # The new library still has its pool and can start threads.
+    RustlerTest.pooled_fac(5)
+    assert_receive 120
+    RustlerTest.erlang_threaded_fac(5)
+    assert_receive {"rustler_test_spawner", 120}
+  end
+end
@@ -0,0 +1,73 @@
+defmodule RustlerTest.YieldTest do
+  use ExUnit.Case, async: true
+
+  test "yielding nif returns the final value" do
+    assert {0, 0} == RustlerTest.yielding_sum(0)
+    assert {sum, _yields} = RustlerTest.yielding_sum(1000)
+    assert sum == div(999 * 1000, 2)
+  end
+
+  test "yielding nif yields on long work" do
+    n = 50_000_000
+    assert {sum, yields} = RustlerTest.yielding_sum(n)
+    assert sum == div((n - 1) * n, 2)
+    assert yields > 0
+  end
+
+  test "yielding nif keeps its state between calls" do
+    assert [] == RustlerTest.yielding_steps(0)
+    assert [0, 1, 2, 3, 4] == RustlerTest.yielding_steps(5)
+  end
+
+  test "yielding nif decodes its arguments" do
+    assert_raise ArgumentError, fn -> RustlerTest.yielding_steps(:not_a_number) end
+  end
+
+  test "switch to a dirty scheduler and back" do
+    assert ["normal"] == RustlerTest.yielding_switch_scheduler(nil)
+
+    assert ["normal", "dirty_cpu", "dirty_cpu", "normal"] ==
+             RustlerTest.yielding_switch_scheduler("dirty_cpu")
+
+    assert ["normal", "dirty_io", "dirty_io", "normal"] ==
+             RustlerTest.yielding_switch_scheduler("dirty_io")
+  end
+
+  test "fold over a large list" do
+    n = 2_000_000
+    assert div(n * (n + 1), 2) == RustlerTest.fold_sum_list(Enum.to_list(1..n))
+    assert 0 == RustlerTest.fold_sum_list([])
+  end
+
+  test "fold over a list with a finishing function" do
+    assert {1_000_000, 1_000_000} == RustlerTest.fold_decode_list(Enum.to_list(1..1_000_000))
+    assert {0, nil} == RustlerTest.fold_decode_list([])
+  end
+
+  test "fold over a list raises on bad elements and improper lists" do
+    assert_raise ArgumentError, fn -> RustlerTest.fold_sum_list(Enum.to_list(1..100_000) ++ [:a]) end
+    assert_raise ArgumentError, fn -> RustlerTest.fold_sum_list([1, 2 | 3]) end
+    assert_raise ArgumentError, fn -> RustlerTest.fold_sum_list(:not_a_list) end
+  end
+
+  test "fold over a large map" do
+    map = Map.new(1..200_000, fn i -> {i, i} end)
+    assert div(200_000 * 200_001, 2) == RustlerTest.fold_sum_map(map)
+    assert_raise ArgumentError, fn -> RustlerTest.fold_sum_map([]) end
+  end
+
+  test "other processes run during a fold" do
+    parent = self()
+    spawn(fn -> send(parent, :ping) end)
+    RustlerTest.fold_sum_list(Enum.to_list(1..2_000_000))
+    assert_received :ping
+  end
+
+  test "other processes run while the entries of a map are collected" do
+    map = Map.new(1..1_000_000, fn i -> {i, i} end)
+    parent = self()
+    spawn(fn -> send(parent, :ping) end)
+    assert div(1_000_000 * 1_000_001, 2) == RustlerTest.fold_sum_map(map)
+    assert_received :ping
+  end
+end